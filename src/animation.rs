@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+use crate::export::ExportSettings;
+use crate::sprite::Sprite;
+
+pub const DEFAULT_FRAME_DURATION_MS: u32 = 100;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub sprite: Sprite,
+    pub duration_ms: u32,
+}
+
+/// A named range of frames, exported as its own animation by formats that
+/// have them (Aseprite tags, Godot SpriteFrames, ...).
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    /// First and last frame of the range, inclusive
+    pub from: usize,
+    pub to: usize,
+    pub looping: bool,
+}
+
+/// The frames of the document being edited. All frames share one size.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Animation {
+    pub frames: Vec<Frame>,
+    pub current: usize,
+    /// Number of times to play the animation, 0 meaning forever (as in GIF)
+    pub loop_count: u16,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    /// Last settings used in the Export dialog for this document
+    #[serde(default)]
+    pub export: ExportSettings,
+}
+
+impl Animation {
+    pub fn from_sprite(sprite: Sprite) -> Self {
+        Self::from_frames(vec![Frame {
+            sprite,
+            duration_ms: DEFAULT_FRAME_DURATION_MS,
+        }])
+    }
+
+    /// Build an animation from decoded frames. `frames` must not be empty.
+    pub fn from_frames(frames: Vec<Frame>) -> Self {
+        assert!(!frames.is_empty(), "An animation needs at least one frame");
+        Self {
+            frames,
+            current: 0,
+            loop_count: 0,
+            tags: Vec::new(),
+            export: ExportSettings::default(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.frames[0].sprite.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.frames[0].sprite.height
+    }
+
+    pub fn current(&self) -> &Sprite {
+        &self.frames[self.current].sprite
+    }
+
+    pub fn current_mut(&mut self) -> &mut Sprite {
+        &mut self.frames[self.current].sprite
+    }
+
+    pub fn current_frame_mut(&mut self) -> &mut Frame {
+        &mut self.frames[self.current]
+    }
+
+    /// Insert a blank frame after the current one and select it.
+    pub fn add_frame(&mut self) {
+        let frame = Frame {
+            sprite: Sprite::new(self.width(), self.height()),
+            duration_ms: self.frames[self.current].duration_ms,
+        };
+        self.insert_after_current(frame);
+    }
+
+    pub fn duplicate_frame(&mut self) {
+        let frame = self.frames[self.current].clone();
+        self.insert_after_current(frame);
+    }
+
+    /// Insert `frames` after the current one, cropped or padded to the
+    /// animation's size, and select the last.
+    pub fn insert_frames(&mut self, frames: Vec<Frame>) {
+        let (width, height) = (self.width(), self.height());
+        for mut frame in frames {
+            if frame.sprite.width != width || frame.sprite.height != height {
+                frame.sprite = frame.sprite.with_size(width, height);
+            }
+            self.insert_after_current(frame);
+        }
+    }
+
+    /// Insert `frame` after the current one and select it. Tags containing
+    /// the current frame grow to include the new one.
+    fn insert_after_current(&mut self, frame: Frame) {
+        for tag in &mut self.tags {
+            if tag.from > self.current {
+                tag.from += 1;
+                tag.to += 1;
+            } else if tag.to >= self.current {
+                tag.to += 1;
+            }
+        }
+        self.current += 1;
+        self.frames.insert(self.current, frame);
+    }
+
+    /// Remove the current frame, keeping at least one. Tags left without
+    /// frames are removed too.
+    pub fn remove_frame(&mut self) {
+        if self.frames.len() > 1 {
+            let index = self.current;
+            self.frames.remove(index);
+            self.current = self.current.min(self.frames.len() - 1);
+            self.tags.retain(|tag| !(tag.from == index && tag.to == index));
+            for tag in &mut self.tags {
+                if tag.from > index {
+                    tag.from -= 1;
+                }
+                if tag.to >= index {
+                    tag.to -= 1;
+                }
+            }
+        }
+    }
+
+    /// The tagged animations, or one looping over every frame when nothing
+    /// is tagged.
+    pub fn tags_or_default(&self) -> Vec<Tag> {
+        if !self.tags.is_empty() {
+            return self.tags.clone();
+        }
+        vec![Tag {
+            name: "default".into(),
+            from: 0,
+            to: self.frames.len() - 1,
+            looping: self.loop_count == 0,
+        }]
+    }
+
+    /// Move the current frame one step earlier (`-1`) or later (`1`). Tags
+    /// keep their frames: a one-frame tag follows its frame, and a tag with
+    /// only one of the two swapped frames grows to cover both.
+    pub fn move_frame(&mut self, step: isize) {
+        let target = self.current as isize + step;
+        if target >= 0 && (target as usize) < self.frames.len() {
+            let target = target as usize;
+            let (first, second) = (self.current.min(target), self.current.max(target));
+            for tag in &mut self.tags {
+                let has_first = tag.from <= first && first <= tag.to;
+                let has_second = tag.from <= second && second <= tag.to;
+                if has_first == has_second {
+                    continue;
+                }
+                if tag.from == tag.to {
+                    let index = if has_first { second } else { first };
+                    tag.from = index;
+                    tag.to = index;
+                } else {
+                    tag.from = tag.from.min(first);
+                    tag.to = tag.to.max(second);
+                }
+            }
+            self.frames.swap(self.current, target);
+            self.current = target;
+        }
+    }
+
+    pub fn next_frame(&mut self) {
+        self.current = (self.current + 1) % self.frames.len();
+    }
+
+    pub fn prev_frame(&mut self) {
+        self.current = (self.current + self.frames.len() - 1) % self.frames.len();
+    }
+}
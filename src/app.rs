@@ -1,26 +1,203 @@
 use egui::Color32;
 
-use crate::canvas::{self, CanvasState};
+use crate::animation::{Animation, Tag};
+use crate::canvas::{self, CanvasState, TileMode};
 use crate::command_palette::{Command, CommandPalette};
+use crate::export;
+use crate::history::{self, History};
 use crate::io;
+use crate::io::code::{CodeOptions, CodeOutput, PixelFormat};
+use crate::io::gif::GifOptions;
+use crate::io::pico8;
+use crate::io::raster::{self, ImageFormat, ImageOptions};
+use crate::io::retro::{self, TileFormat};
+use crate::io::sheet::{JsonFormat, SheetLayout, SheetOptions, SliceMode, SliceOptions};
+use crate::io::tiled::TiledOptions;
+use crate::iso::{self, HeightMap};
+use crate::notifications::Notifications;
+use crate::recovery;
+use crate::selection::{self, Floating};
+use crate::session::{self, Session, Source, ViewState};
 use crate::sprite::Sprite;
+use crate::tileset::Tileset;
 use crate::tools::{self, Tool};
 
+/// Asked of the recovery prompt or dialog, carried out in `autosave` where
+/// eframe storage is at hand.
+enum RecoveryRequest {
+    /// Reopen each snapshot in its own tab
+    Restore(Vec<recovery::Snapshot>),
+    DiscardAll,
+}
+
+/// Closing that would throw away unsaved changes, held while the user
+/// decides whether to save them first.
+enum PendingClose {
+    /// The active tab
+    Tab,
+    /// Every tab but the active one
+    Others,
+    #[cfg(not(target_arch = "wasm32"))]
+    Window,
+}
+
+/// A document waiting in the tab strip. The active document's state lives
+/// directly on `SpriteditApp` and is swapped with a `Tab` on switching.
+struct Tab {
+    animation: Animation,
+    heights: HeightMap,
+    tileset: Tileset,
+    canvas_state: CanvasState,
+    selected_tile: u32,
+    palette: Option<Vec<[u8; 4]>>,
+    palette_prompt: Option<Vec<[u8; 4]>>,
+    png_palette: Option<Vec<[u8; 4]>>,
+    png_text: Vec<(String, String)>,
+    pico8_cart: Option<pico8::Cart>,
+    source: Option<Source>,
+    dirty: bool,
+    history: History,
+    document_id: u64,
+    autosave_fingerprint: u64,
+}
+
+impl Tab {
+    /// The untitled 16x16 sprite a new tab starts with.
+    fn blank(document_id: u64) -> Self {
+        Self {
+            animation: Animation::from_sprite(Sprite::new(16, 16)),
+            heights: HeightMap::new(16, 16),
+            tileset: Tileset::default(),
+            canvas_state: CanvasState::default(),
+            selected_tile: 0,
+            palette: None,
+            palette_prompt: None,
+            png_palette: None,
+            png_text: Vec::new(),
+            pico8_cart: None,
+            source: None,
+            dirty: false,
+            history: History::default(),
+            document_id,
+            autosave_fingerprint: 0,
+        }
+    }
+
+    fn recovery_document(&self) -> recovery::Document {
+        recovery::Document {
+            animation: self.animation.clone(),
+            heights: self.heights.clone(),
+            tileset: self.tileset.clone(),
+            palette: self.palette.clone(),
+            png_palette: self.png_palette.clone(),
+            png_text: self.png_text.clone(),
+            pixels_per_grid: self.canvas_state.pixels_per_grid,
+            source: self.source.clone(),
+        }
+    }
+}
+
+/// Tab and title name of a document, from where it came from.
+fn source_name(source: Option<&Source>) -> String {
+    source.map_or_else(|| "Untitled".into(), Source::label)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const PNG_FILTER: (&str, &[&str]) = ("PNG Image", &["png"]);
+
 pub struct SpriteditApp {
-    sprite: Sprite,
+    animation: Animation,
+    heights: HeightMap,
+    tileset: Tileset,
     canvas_state: CanvasState,
     current_tool: Tool,
     primary_color: Color32,
+    // Colors kept from an indexed PNG, offered as swatches
+    palette: Option<Vec<[u8; 4]>>,
+    // Palette of a just-opened indexed PNG, waiting for the user to keep it
+    palette_prompt: Option<Vec<[u8; 4]>>,
+    // PLTE order of the loaded PNG, kept for saving whether or not its
+    // colors were kept as swatches
+    png_palette: Option<Vec<[u8; 4]>>,
+    // tEXt chunks of the loaded PNG, written back on save
+    png_text: Vec<(String, String)>,
     command_palette: CommandPalette,
 
-    // For smooth painting — track last painted pixel
-    last_paint_pos: Option<(u32, u32)>,
+    // For smooth painting — track last painted canvas pixel
+    last_paint_pos: Option<(i32, i32)>,
+
+    // Block tools — cells already raised or lowered during the current stroke
+    height_step: u8,
+    height_stroke: Vec<(u32, u32)>,
+
+    // Select tool and clipboard
+    selection_drag: Option<selection::Drag>,
+    #[cfg(not(target_arch = "wasm32"))]
+    clipboard: io::clipboard::native::Clipboard,
+    /// Whether the clipboard image on its way should open as a new sprite
+    #[cfg(target_arch = "wasm32")]
+    pending_paste_as_sprite: bool,
 
     // New sprite dialog
     show_new_dialog: bool,
     new_width: String,
     new_height: String,
 
+    // Animation playback
+    playing: bool,
+    play_elapsed_ms: f32,
+
+    // Scaled export dialog; settings live on the document
+    show_export_dialog: bool,
+
+    // GIF export dialog
+    show_gif_dialog: bool,
+    gif_options: GifOptions,
+
+    // Sprite sheet export dialog
+    show_sheet_dialog: bool,
+    sheet_options: SheetOptions,
+
+    // Tile properties — tile being edited and whether to overlay it
+    selected_tile: u32,
+    show_tile_overlay: bool,
+
+    // BMP / TGA / QOI / icon export dialog
+    show_image_dialog: bool,
+    image_options: ImageOptions,
+
+    // Source code export dialog
+    show_code_dialog: bool,
+    code_options: CodeOptions,
+
+    // Retro tile import/export dialog
+    show_retro_dialog: bool,
+    retro_format: TileFormat,
+    retro_palette: Vec<[u8; 4]>,
+    retro_dedup: bool,
+    // Set while the browser's file picker is open for a tile import
+    #[cfg(target_arch = "wasm32")]
+    pending_tile_import: bool,
+
+    // PICO-8 cart the sprite came from, kept so saving only rewrites the
+    // sprite sheet and flags
+    pico8_cart: Option<pico8::Cart>,
+
+    // Tiled export dialog
+    show_tiled_dialog: bool,
+    tiled_options: TiledOptions,
+
+    // Sprite sheet slicing dialog
+    show_slice_dialog: bool,
+    slice_options: SliceOptions,
+    /// The options and frame the current cut was worked out from, and the
+    /// cut, so auto-detect only reruns when one of them changes
+    slice_preview: Option<(SliceOptions, Sprite, Vec<io::sheet::Rect>)>,
+
+    // New isometric tile dialog
+    show_iso_tile_dialog: bool,
+    iso_tile_size: (u32, u32),
+
     // URL load dialog
     show_url_dialog: bool,
     url_input: String,
@@ -29,34 +206,231 @@ pub struct SpriteditApp {
     show_ai_dialog: bool,
     ai_prompt: String,
 
+    // Recent files and session restore
+    session: Session,
+    /// Where the open document came from, if it can be opened again
+    source: Option<Source>,
+    /// View to restore once the last session's document has loaded
+    restore_view: Option<ViewState>,
+    /// URL being fetched, remembered as the document's source once it lands
+    #[cfg(target_arch = "wasm32")]
+    pending_url: Option<String>,
+
+    // Autosave and crash recovery
+    show_autosave_dialog: bool,
+    /// Newest snapshot of each document left unsaved by the last run,
+    /// offered for restore on startup
+    recovery_prompt: Vec<recovery::Snapshot>,
+    /// Recovery snapshots in storage, newest first
+    snapshots: Vec<recovery::Snapshot>,
+    /// Keys the active document's snapshots; each tab has its own
+    document_id: u64,
+    next_document_id: u64,
+    /// Documents saved or closed since the last autosave, whose snapshots
+    /// are no longer needed
+    stale_snapshots: Vec<u64>,
+    /// Fingerprint of the document when last loaded, saved or snapshotted
+    autosave_fingerprint: u64,
+    /// `InputState::time` of the next snapshot
+    next_autosave: f64,
+    recovery_request: Option<RecoveryRequest>,
+
+    // Unsaved changes
+    /// The document changed since it was loaded, created or saved
+    dirty: bool,
+    /// Undo and redo steps of the active document
+    history: History,
+    unsaved_prompt: Option<PendingClose>,
+    /// Set once the user agreed to close without saving
+    #[cfg(not(target_arch = "wasm32"))]
+    allow_close: bool,
+    /// Window title last sent, so it's only sent when it changes
+    title: String,
+
+    // Open documents; the active one's slot is empty while its state is
+    // on the app
+    tabs: Vec<Option<Tab>>,
+    active_tab: usize,
+
     // Status
     status_message: String,
+    notifications: Notifications,
 }
 
 impl SpriteditApp {
     pub fn new(cc: &eframe::CreationContext) -> Self {
         cc.egui_ctx.set_visuals(egui::Visuals::dark());
-        Self {
-            sprite: Sprite::new(16, 16),
+        let session: Session = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, session::STORAGE_KEY))
+            .unwrap_or_default();
+        let first_document_id = recovery::first_document_id();
+        let mut app = Self {
+            animation: Animation::from_sprite(Sprite::new(16, 16)),
+            heights: HeightMap::new(16, 16),
+            tileset: Tileset::default(),
             canvas_state: CanvasState::default(),
             current_tool: Tool::Pencil,
             primary_color: Color32::from_rgb(255, 255, 255),
+            palette: None,
+            palette_prompt: None,
+            png_palette: None,
+            png_text: Vec::new(),
             command_palette: CommandPalette::default(),
             last_paint_pos: None,
+            height_step: 1,
+            height_stroke: Vec::new(),
+            selection_drag: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            clipboard: Default::default(),
+            #[cfg(target_arch = "wasm32")]
+            pending_paste_as_sprite: false,
             show_new_dialog: false,
             new_width: "16".into(),
             new_height: "16".into(),
+            playing: false,
+            play_elapsed_ms: 0.0,
+            show_export_dialog: false,
+            show_gif_dialog: false,
+            gif_options: GifOptions::default(),
+            show_sheet_dialog: false,
+            sheet_options: SheetOptions::default(),
+            selected_tile: 0,
+            show_tile_overlay: true,
+            show_image_dialog: false,
+            image_options: ImageOptions::default(),
+            show_code_dialog: false,
+            code_options: CodeOptions::default(),
+            show_retro_dialog: false,
+            retro_format: TileFormat::NesChr,
+            retro_palette: retro::DEFAULT_PALETTE.to_vec(),
+            retro_dedup: false,
+            #[cfg(target_arch = "wasm32")]
+            pending_tile_import: false,
+            pico8_cart: None,
+            show_tiled_dialog: false,
+            tiled_options: TiledOptions::default(),
+            show_slice_dialog: false,
+            slice_options: SliceOptions::default(),
+            slice_preview: None,
+            show_iso_tile_dialog: false,
+            iso_tile_size: (32, 16),
             show_url_dialog: false,
             url_input: String::new(),
             show_ai_dialog: false,
             ai_prompt: String::new(),
+            session,
+            source: None,
+            restore_view: None,
+            #[cfg(target_arch = "wasm32")]
+            pending_url: None,
+            show_autosave_dialog: false,
+            recovery_prompt: Vec::new(),
+            snapshots: Vec::new(),
+            document_id: first_document_id,
+            next_document_id: first_document_id + 1,
+            stale_snapshots: Vec::new(),
+            autosave_fingerprint: 0,
+            next_autosave: 0.0,
+            recovery_request: None,
+            dirty: false,
+            history: History::default(),
+            unsaved_prompt: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            allow_close: false,
+            title: String::new(),
+            tabs: vec![None],
+            active_tab: 0,
             status_message: "Ready".into(),
+            notifications: Notifications::default(),
+        };
+        if app.session.reopen_last {
+            app.restore_session();
+        }
+        app.mark_clean();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            app.snapshots = recovery::native::list();
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            app.snapshots = cc.storage.map(recovery::web::list).unwrap_or_default();
+        }
+        app.recovery_prompt = recovery::latest_per_document(&app.snapshots);
+        app
+    }
+
+    /// Reopen the document and view from the last run. The view waits for
+    /// the document, which may still be downloading.
+    fn restore_session(&mut self) {
+        let view = self.session.view.clone();
+        match self.session.last.clone() {
+            Some(source) => {
+                self.restore_view = view;
+                self.open_recent(source);
+            }
+            None => {
+                if let Some(view) = view {
+                    view.apply(&mut self.canvas_state);
+                }
+            }
+        }
+    }
+
+    /// Reopen a file or URL from the recent list.
+    fn open_recent(&mut self, source: Source) {
+        match &source {
+            #[cfg(not(target_arch = "wasm32"))]
+            Source::File(path) => match io::native::read_file(path) {
+                Ok(data) => self.load_from_source(&data, source),
+                Err(e) => {
+                    self.restore_view = None;
+                    self.notifications.error(e);
+                }
+            },
+            // Browsers can't reopen local files by path, so none are recorded
+            #[cfg(target_arch = "wasm32")]
+            Source::File(_) => self.restore_view = None,
+            Source::Url(url) => self.load_from_url(&url.clone()),
+        }
+    }
+
+    /// Load a file's contents and remember where it came from.
+    fn load_from_source(&mut self, data: &[u8], source: Source) {
+        if !self.load_bytes(data, &format!(" from {}", source.label())) {
+            self.restore_view = None;
+            return;
+        }
+        self.session.add_recent(source.clone());
+        self.source = Some(source);
+        self.mark_clean();
+        if let Some(view) = self.restore_view.take() {
+            view.apply(&mut self.canvas_state);
         }
     }
 
     fn handle_shortcuts(&mut self, ctx: &egui::Context) {
-        // Don't handle tool shortcuts while command palette or dialogs are open
-        if self.command_palette.is_open || self.show_new_dialog || self.show_url_dialog || self.show_ai_dialog {
+        // Don't handle tool shortcuts while command palette or dialogs are
+        // open, or while typing into a text field
+        if self.command_palette.is_open
+            || ctx.wants_keyboard_input()
+            || self.show_new_dialog
+            || self.show_iso_tile_dialog
+            || self.show_export_dialog
+            || self.show_gif_dialog
+            || self.show_sheet_dialog
+            || self.show_slice_dialog
+            || self.show_tiled_dialog
+            || self.show_image_dialog
+            || self.show_code_dialog
+            || self.show_retro_dialog
+            || self.show_url_dialog
+            || self.show_ai_dialog
+            || self.show_autosave_dialog
+            || !self.recovery_prompt.is_empty()
+            || self.unsaved_prompt.is_some()
+            || self.palette_prompt.is_some()
+        {
             return;
         }
 
@@ -76,9 +450,44 @@ impl SpriteditApp {
             if cmd && !shift && i.key_pressed(egui::Key::O) {
                 self.open_file();
             }
+            if cmd && !shift && i.key_pressed(egui::Key::W) {
+                self.close_tab(self.active_tab);
+            }
             if cmd && !shift && i.key_pressed(egui::Key::S) {
                 self.save_file();
             }
+            if cmd && shift && i.key_pressed(egui::Key::S) {
+                self.save_file_as();
+            }
+            if cmd && !shift && i.key_pressed(egui::Key::E) {
+                self.show_export_dialog = true;
+            }
+            if cmd && !shift && i.key_pressed(egui::Key::Z) {
+                self.undo();
+            }
+            if cmd && (shift && i.key_pressed(egui::Key::Z) || i.key_pressed(egui::Key::Y)) {
+                self.redo();
+            }
+
+            // Clipboard. Copy comes as its own event; winit swallows the
+            // Ctrl+V press when the clipboard holds no text, so paste
+            // triggers on the key release instead.
+            if i.events.iter().any(|e| matches!(e, egui::Event::Copy)) {
+                self.copy_to_clipboard();
+            }
+            for event in &i.events {
+                if let egui::Event::Key {
+                    key: egui::Key::V,
+                    pressed: false,
+                    modifiers,
+                    ..
+                } = event
+                {
+                    if modifiers.command {
+                        self.paste_from_clipboard(modifiers.shift);
+                    }
+                }
+            }
 
             // Tool shortcuts (only when no modifier)
             if !cmd && !shift && !i.modifiers.alt {
@@ -94,12 +503,40 @@ impl SpriteditApp {
                 if i.key_pressed(egui::Key::I) {
                     self.current_tool = Tool::ColorPicker;
                 }
+                if i.key_pressed(egui::Key::R) {
+                    self.current_tool = Tool::Raise;
+                }
+                if i.key_pressed(egui::Key::L) {
+                    self.current_tool = Tool::Lower;
+                }
+                if i.key_pressed(egui::Key::M) {
+                    self.current_tool = Tool::Select;
+                }
+                if i.key_pressed(egui::Key::Escape) {
+                    self.drop_floating();
+                    self.canvas_state.selection = None;
+                }
                 if i.key_pressed(egui::Key::G) {
                     self.canvas_state.show_grid = !self.canvas_state.show_grid;
                 }
                 if i.key_pressed(egui::Key::V) {
                     self.canvas_state.isometric = !self.canvas_state.isometric;
                 }
+                if i.key_pressed(egui::Key::B) {
+                    self.canvas_state.blocks = !self.canvas_state.blocks;
+                }
+                if i.key_pressed(egui::Key::T) {
+                    self.canvas_state.tiled = !self.canvas_state.tiled;
+                }
+                if i.key_pressed(egui::Key::Comma) {
+                    self.animation.prev_frame();
+                }
+                if i.key_pressed(egui::Key::Period) {
+                    self.animation.next_frame();
+                }
+                if i.key_pressed(egui::Key::Enter) {
+                    self.toggle_playback();
+                }
             }
         });
     }
@@ -107,19 +544,74 @@ impl SpriteditApp {
     fn execute_command(&mut self, command: Command) {
         match command {
             Command::NewSprite => self.show_new_dialog = true,
+            Command::NewIsoTile => self.show_iso_tile_dialog = true,
             Command::OpenFile => self.open_file(),
             Command::LoadFromURL => self.show_url_dialog = true,
-            Command::SaveFile => self.save_file(),
+            Command::ImportSheet => self.import_sheet(),
+            Command::SliceSheet => self.show_slice_dialog = true,
+            Command::SaveFile => {
+                self.save_file();
+            }
+            Command::SaveAs => {
+                self.save_file_as();
+            }
+            Command::ExportIsoBlock => self.export_iso_block(),
+            Command::ExportGif => self.show_gif_dialog = true,
+            Command::ExportScaled => self.show_export_dialog = true,
+            Command::ExportImage => self.show_image_dialog = true,
+            Command::ExportApng => self.export_apng(),
+            Command::ExportWebp => self.export_webp(),
+            Command::ExportSheet => self.show_sheet_dialog = true,
+            Command::ExportTiled => self.show_tiled_dialog = true,
+            Command::ExportGodot => self.export_godot(),
+            Command::ExportCode => self.show_code_dialog = true,
+            Command::RetroTiles => self.show_retro_dialog = true,
+            Command::ExportPico8 => self.export_pico8(),
+            Command::ExportAseprite => self.export_aseprite(),
+            Command::ExportIsoTile => self.export_iso_tile(),
+            Command::ConvertToIsometric => self.convert_to_isometric(),
             Command::ToggleGrid => {
                 self.canvas_state.show_grid = !self.canvas_state.show_grid;
             }
             Command::ToggleIsometric => {
                 self.canvas_state.isometric = !self.canvas_state.isometric;
             }
+            Command::ToggleBlocks => {
+                self.canvas_state.blocks = !self.canvas_state.blocks;
+            }
+            Command::ToggleFootprintLock => {
+                self.canvas_state.lock_footprint = !self.canvas_state.lock_footprint;
+            }
+            Command::ToggleTiled => {
+                self.canvas_state.tiled = !self.canvas_state.tiled;
+            }
+            Command::AddFrame => {
+                self.animation.add_frame();
+                self.dirty = true;
+            }
+            Command::DuplicateFrame => {
+                self.animation.duplicate_frame();
+                self.dirty = true;
+            }
+            Command::DeleteFrame => {
+                self.animation.remove_frame();
+                self.dirty = true;
+            }
+            Command::PrevFrame => self.animation.prev_frame(),
+            Command::NextFrame => self.animation.next_frame(),
+            Command::TogglePlayback => self.toggle_playback(),
             Command::SetPencil => self.current_tool = Tool::Pencil,
             Command::SetEraser => self.current_tool = Tool::Eraser,
             Command::SetFill => self.current_tool = Tool::Fill,
             Command::SetColorPicker => self.current_tool = Tool::ColorPicker,
+            Command::SetRaise => self.current_tool = Tool::Raise,
+            Command::SetLower => self.current_tool = Tool::Lower,
+            Command::SetSelect => self.current_tool = Tool::Select,
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::Copy => self.copy_to_clipboard(),
+            Command::Paste => self.paste_from_clipboard(false),
+            Command::PasteAsSprite => self.paste_from_clipboard(true),
             Command::ZoomIn => {
                 self.canvas_state.zoom = (self.canvas_state.zoom * 1.5).min(128.0)
             }
@@ -131,210 +623,1913 @@ impl SpriteditApp {
                 self.canvas_state.offset = egui::Vec2::ZERO;
             }
             Command::GenerateAI => self.show_ai_dialog = true,
+            Command::OpenRecent(index) => {
+                if let Some(source) = self.session.recent.get(index).cloned() {
+                    self.open_recent(source);
+                }
+            }
+            Command::NewTab => self.new_tab(),
+            Command::CloseTab => self.close_tab(self.active_tab),
+            Command::CloseOtherTabs => self.close_other_tabs(),
+            Command::ClearRecent => self.session.recent.clear(),
+            Command::Autosave => self.show_autosave_dialog = true,
+            Command::ToggleReopenLast => {
+                self.session.reopen_last = !self.session.reopen_last;
+            }
         }
     }
 
     fn open_file(&mut self) {
         #[cfg(not(target_arch = "wasm32"))]
         {
-            if let Some(data) = io::native::open_file_dialog() {
-                if let Some(sprite) = io::png_to_sprite(&data) {
-                    self.status_message =
-                        format!("Loaded {}x{} sprite", sprite.width, sprite.height);
-                    self.sprite = sprite;
-                    self.canvas_state.offset = egui::Vec2::ZERO;
-                } else {
-                    self.status_message = "Failed to decode image".into();
-                }
+            match io::native::open_file_dialog() {
+                Ok(Some((path, data))) => self.load_from_source(&data, Source::File(path)),
+                Ok(None) => {}
+                Err(e) => self.notifications.error(e),
             }
         }
         #[cfg(target_arch = "wasm32")]
         {
-            io::web::open_file_dialog();
-            self.status_message = "Opening file...".into();
+            match io::web::open_file_dialog() {
+                Ok(()) => self.status_message = "Opening file...".into(),
+                Err(e) => self.notifications.error(e),
+            }
         }
     }
 
-    fn save_file(&mut self) {
-        let png_data = io::sprite_to_png(&self.sprite);
+    /// Open an image and go straight to slicing it into frames.
+    fn import_sheet(&mut self) {
         #[cfg(not(target_arch = "wasm32"))]
         {
-            if io::native::save_file_dialog(&png_data) {
-                self.status_message = "Sprite saved".into();
-            } else {
-                self.status_message = "Save cancelled".into();
+            match io::native::open_file_dialog() {
+                Ok(Some((path, data))) => {
+                    self.load_from_source(&data, Source::File(path));
+                    self.show_slice_dialog = true;
+                }
+                Ok(None) => {}
+                Err(e) => self.notifications.error(e),
             }
         }
         #[cfg(target_arch = "wasm32")]
         {
-            io::web::save_file(&png_data, "sprite.png");
-            self.status_message = "Downloading sprite...".into();
+            // The file arrives asynchronously; the dialog previews whatever
+            // is loaded, so it picks the sheet up once it lands.
+            match io::web::open_file_dialog() {
+                Ok(()) => {
+                    self.show_slice_dialog = true;
+                    self.status_message = "Opening sprite sheet...".into();
+                }
+                Err(e) => self.notifications.error(e),
+            }
         }
     }
 
-    fn check_pending_file(&mut self) {
+    fn import_tiles(&mut self) {
+        let format = self.retro_format;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = (format.name(), &[format.extension(), "bin"][..]);
+            match io::native::open_file_dialog_filtered(filter) {
+                Ok(Some(data)) => self.load_tiles(&data),
+                Ok(None) => {}
+                Err(e) => self.notifications.error(e),
+            }
+        }
         #[cfg(target_arch = "wasm32")]
         {
-            if let Some(data) = io::web::check_pending_file() {
-                if let Some(sprite) = io::png_to_sprite(&data) {
-                    self.status_message =
-                        format!("Loaded {}x{} sprite", sprite.width, sprite.height);
-                    self.sprite = sprite;
-                    self.canvas_state.offset = egui::Vec2::ZERO;
-                } else {
-                    self.status_message = "Failed to decode image".into();
+            match io::web::open_file_dialog_accepting(&format!(".{},.bin", format.extension())) {
+                Ok(()) => {
+                    self.pending_tile_import = true;
+                    self.status_message = format!("Opening {} tiles...", format.name());
                 }
+                Err(e) => self.notifications.error(e),
             }
         }
     }
 
-    fn apply_tool_at(&mut self, x: u32, y: u32) {
-        let color_arr = [
-            self.primary_color.r(),
-            self.primary_color.g(),
-            self.primary_color.b(),
-            self.primary_color.a(),
-        ];
+    /// Decode a tile file with the retro dialog's format and palette.
+    fn load_tiles(&mut self, data: &[u8]) {
+        let format = self.retro_format;
+        match retro::import(data, format, &self.retro_palette) {
+            Ok(sprite) => {
+                self.status_message = format!(
+                    "Loaded {} {} tile(s)",
+                    data.len() / format.tile_bytes(),
+                    format.name()
+                );
+                self.open_sprite(sprite);
+                self.canvas_state.pixels_per_grid = retro::TILE_SIZE;
+                self.mark_clean();
+            }
+            Err(e) => self.notifications.error(e),
+        }
+    }
 
-        match self.current_tool {
-            Tool::Pencil => {
-                self.sprite.set_pixel(x, y, color_arr);
+    /// Decode a loaded file, picking the format from its contents. `source`
+    /// is appended to the status message, e.g. " from URL". Returns whether
+    /// the file was loaded.
+    fn load_bytes(&mut self, data: &[u8], source: &str) -> bool {
+        if pico8::is_p8(data) {
+            match pico8::read(data) {
+                Ok((cart, sprite)) => {
+                    self.status_message = format!("Loaded PICO-8 sprite sheet{}", source);
+                    self.open_sprite(sprite);
+                    self.pico8_cart = Some(cart);
+                    self.canvas_state.pixels_per_grid = pico8::TILE_SIZE;
+                }
+                Err(e) => {
+                    self.notifications.error(e);
+                    return false;
+                }
             }
-            Tool::Eraser => {
-                self.sprite.set_pixel(x, y, [0, 0, 0, 0]);
+        } else if io::aseprite::is_aseprite(data) {
+            match io::aseprite::read(data) {
+                Ok(import) => {
+                    let animation = import.animation;
+                    self.status_message = format!(
+                        "Loaded {}x{} Aseprite sprite with {} frame(s){}",
+                        animation.width(),
+                        animation.height(),
+                        animation.frames.len(),
+                        source
+                    );
+                    if !import.lost.is_empty() {
+                        self.status_message += &format!(" ({})", import.lost.join("; "));
+                    }
+                    self.open_animation(animation);
+                }
+                Err(e) => {
+                    self.notifications.error(e);
+                    return false;
+                }
             }
-            Tool::Fill => {
-                self.sprite.flood_fill(x, y, color_arr);
+        } else if io::gif::is_gif(data) {
+            match io::gif::read(data) {
+                Ok(animation) => {
+                    self.status_message = format!(
+                        "Loaded {}x{} GIF with {} frame(s){}",
+                        animation.width(),
+                        animation.height(),
+                        animation.frames.len(),
+                        source
+                    );
+                    self.open_animation(animation);
+                }
+                Err(e) => {
+                    self.notifications.error(e);
+                    return false;
+                }
             }
-            Tool::ColorPicker => {
-                let [r, g, b, a] = self.sprite.get_pixel(x, y);
-                self.primary_color = Color32::from_rgba_unmultiplied(r, g, b, a);
-                self.current_tool = Tool::Pencil;
+        } else {
+            let sprite = match io::decode_image(data) {
+                Ok(sprite) => sprite,
+                Err(e) => {
+                    self.notifications.error(e);
+                    return false;
+                }
+            };
+            self.status_message =
+                format!("Loaded {}x{} sprite{}", sprite.width, sprite.height, source);
+            self.open_sprite(sprite);
+            if let Some(info) = io::png_info(data) {
+                if info.sixteen_bit {
+                    self.status_message += " (16-bit PNG reduced to 8 bits per channel)";
+                }
+                if let Some(colors) = info.palette {
+                    self.status_message += &format!(" (indexed PNG, {} colors)", colors.len());
+                    self.png_palette = Some(colors.clone());
+                    self.palette_prompt = Some(colors);
+                }
+            }
+            let meta = io::png_file::read_meta(data);
+            self.png_text = meta.text;
+            if let Some(metadata) = meta.metadata {
+                self.canvas_state.pixels_per_grid = metadata.grid.clamp(1, 32);
+                self.animation.frames[0].duration_ms = metadata.duration_ms.max(1);
+                let mut export = metadata.export;
+                export.scale = export.scale.clamp(1, export::MAX_SCALE);
+                export.padding = export.padding.min(256);
+                self.animation.export = export;
             }
         }
+        self.mark_clean();
+        true
     }
 
-    fn handle_canvas_response(&mut self, response: canvas::CanvasResponse) {
-        // Update status with hover position
-        if let Some((x, y)) = response.hovered_pixel {
-            let [r, g, b, a] = self.sprite.get_pixel(x, y);
-            self.status_message = format!(
-                "({}, {})  RGBA({}, {}, {}, {})",
-                x, y, r, g, b, a
-            );
+    /// Open files dropped on the window, each in its own tab, or append
+    /// them as frames when dropped on the timeline.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context, timeline: egui::Rect) {
+        let (dropped, pos) = ctx.input(|i| (i.raw.dropped_files.clone(), i.pointer.latest_pos()));
+        if dropped.is_empty() {
+            return;
         }
-
-        // Handle painting with line interpolation
-        if !response.painted_pixels.is_empty() {
-            for &(x, y) in &response.painted_pixels {
-                // Interpolate from last position for smooth lines
-                if let Some((lx, ly)) = self.last_paint_pos {
-                    let line = tools::line_pixels(
-                        lx as i32, ly as i32, x as i32, y as i32,
-                    );
-                    for (px, py) in line {
-                        if px >= 0
-                            && py >= 0
-                            && (px as u32) < self.sprite.width
-                            && (py as u32) < self.sprite.height
-                        {
-                            self.apply_tool_at(px as u32, py as u32);
-                        }
+        let as_frames = pos.is_some_and(|p| timeline.contains(p));
+        for file in &dropped {
+            let name = dropped_file_name(file);
+            let data = match dropped_file_bytes(file) {
+                Ok(data) => data,
+                Err(e) => {
+                    self.notifications.error(e);
+                    continue;
+                }
+            };
+            if as_frames {
+                self.add_frames(&data, &name);
+            } else {
+                match &file.path {
+                    Some(path) => self.load_from_source(&data, Source::File(path.clone())),
+                    None => {
+                        self.load_bytes(&data, &format!(" from {name}"));
                     }
-                } else {
-                    self.apply_tool_at(x, y);
                 }
-                self.last_paint_pos = Some((x, y));
             }
-        } else {
-            self.last_paint_pos = None;
         }
+    }
 
-        // Handle right-click color pick
-        if let Some([r, g, b, a]) = response.picked_color {
-            self.primary_color = Color32::from_rgba_unmultiplied(r, g, b, a);
-            self.status_message = format!("Picked RGBA({}, {}, {}, {})", r, g, b, a);
+    /// Append every frame of a file after the current one.
+    fn add_frames(&mut self, data: &[u8], name: &str) {
+        let animation = match io::decode_animation(data) {
+            Ok(animation) => animation,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        let (w, h) = (animation.width(), animation.height());
+        let count = animation.frames.len();
+        self.animation.insert_frames(animation.frames);
+        self.playing = false;
+        self.dirty = true;
+        self.status_message = format!("Added {count} frame(s) from {name}");
+        if (w, h) != (self.animation.width(), self.animation.height()) {
+            self.status_message += &format!(" (resized from {w}x{h})");
         }
     }
 
-    fn show_tool_panel(&mut self, ui: &mut egui::Ui) {
-        ui.vertical_centered(|ui| {
-            ui.heading("Tools");
-            ui.separator();
+    /// Outline where hovering files would land: the timeline adds frames,
+    /// anywhere else opens.
+    fn show_drop_target(&self, ctx: &egui::Context, canvas: egui::Rect, timeline: egui::Rect) {
+        let (hovering, pos) =
+            ctx.input(|i| (!i.raw.hovered_files.is_empty(), i.pointer.latest_pos()));
+        if !hovering {
+            return;
+        }
+        let (rect, text) = if pos.is_some_and(|p| timeline.contains(p)) {
+            (timeline, "Drop to add frames")
+        } else {
+            (canvas, "Drop to open")
+        };
+        let accent = Color32::from_rgb(90, 150, 255);
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("drop_target"),
+        ));
+        painter.rect(
+            rect.shrink(2.0),
+            4.0,
+            accent.gamma_multiply(0.15),
+            egui::Stroke::new(2.0, accent),
+        );
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            text,
+            egui::FontId::proportional(18.0),
+            Color32::WHITE,
+        );
+    }
 
-            let tools = [Tool::Pencil, Tool::Eraser, Tool::Fill, Tool::ColorPicker];
-            for tool in tools {
-                let selected = self.current_tool == tool;
-                let text = format!("{} {}", tool.icon(), tool.shortcut());
-                if ui
-                    .selectable_label(selected, &text)
-                    .on_hover_text(tool.name())
-                    .clicked()
-                {
-                    self.current_tool = tool;
-                }
-            }
-        });
+    /// Open a single-frame document, like `open_animation`.
+    fn open_sprite(&mut self, sprite: Sprite) {
+        self.open_animation(Animation::from_sprite(sprite));
     }
 
-    fn show_properties_panel(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Properties");
-        ui.separator();
+    /// Swap the active document's state with `tab`'s.
+    fn swap_tab(&mut self, tab: &mut Tab) {
+        // Changes from a stroke still held down belong to the outgoing tab
+        self.check_in_history();
+        std::mem::swap(&mut self.animation, &mut tab.animation);
+        std::mem::swap(&mut self.heights, &mut tab.heights);
+        std::mem::swap(&mut self.tileset, &mut tab.tileset);
+        std::mem::swap(&mut self.canvas_state, &mut tab.canvas_state);
+        std::mem::swap(&mut self.selected_tile, &mut tab.selected_tile);
+        std::mem::swap(&mut self.palette, &mut tab.palette);
+        std::mem::swap(&mut self.palette_prompt, &mut tab.palette_prompt);
+        std::mem::swap(&mut self.png_palette, &mut tab.png_palette);
+        std::mem::swap(&mut self.png_text, &mut tab.png_text);
+        std::mem::swap(&mut self.pico8_cart, &mut tab.pico8_cart);
+        std::mem::swap(&mut self.source, &mut tab.source);
+        std::mem::swap(&mut self.dirty, &mut tab.dirty);
+        std::mem::swap(&mut self.history, &mut tab.history);
+        std::mem::swap(&mut self.document_id, &mut tab.document_id);
+        std::mem::swap(&mut self.autosave_fingerprint, &mut tab.autosave_fingerprint);
+        // Strokes, drags and playback don't carry over
+        self.playing = false;
+        self.last_paint_pos = None;
+        self.height_stroke.clear();
+        self.selection_drag = None;
+    }
 
-        // Color picker
-        ui.label("Color");
-        ui.color_edit_button_srgba(&mut self.primary_color);
-        ui.add_space(8.0);
+    fn blank_tab(&mut self) -> Tab {
+        self.next_document_id += 1;
+        Tab::blank(self.next_document_id - 1)
+    }
 
-        // Alpha slider
-        let mut alpha = self.primary_color.a() as f32 / 255.0;
-        if ui
-            .add(egui::Slider::new(&mut alpha, 0.0..=1.0).text("Alpha"))
-            .changed()
-        {
-            let [r, g, b, _] = self.primary_color.to_array();
-            self.primary_color =
-                Color32::from_rgba_unmultiplied(r, g, b, (alpha * 255.0) as u8);
+    /// Start a blank document in a new tab at the end of the strip.
+    fn new_tab(&mut self) {
+        let mut tab = self.blank_tab();
+        self.swap_tab(&mut tab);
+        self.tabs[self.active_tab] = Some(tab);
+        self.tabs.push(None);
+        self.active_tab = self.tabs.len() - 1;
+        self.mark_clean();
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab {
+            return;
         }
+        let mut tab = self.tabs[index].take().expect("inactive tabs hold their document");
+        self.swap_tab(&mut tab);
+        self.tabs[self.active_tab] = Some(tab);
+        self.active_tab = index;
+    }
 
-        ui.add_space(12.0);
-        ui.separator();
+    /// Name and unsaved flag of the document in tab `index`.
+    fn tab_info(&self, index: usize) -> (String, bool) {
+        match &self.tabs[index] {
+            Some(tab) => (source_name(tab.source.as_ref()), tab.dirty),
+            None => (self.document_name(), self.dirty),
+        }
+    }
 
-        // Sprite info
-        ui.label("Sprite");
-        ui.label(format!(
-            "Size: {} x {}",
-            self.sprite.width, self.sprite.height
-        ));
-        ui.add_space(4.0);
+    /// Close tab `index`, showing it and asking first if it has unsaved
+    /// changes.
+    fn close_tab(&mut self, index: usize) {
+        if self.tab_info(index).1 {
+            self.switch_tab(index);
+            self.unsaved_prompt = Some(PendingClose::Tab);
+        } else {
+            self.remove_tab(index);
+        }
+    }
 
-        // Pixels per grid
-        ui.label("Pixels per grid box");
-        let mut ppg = self.canvas_state.pixels_per_grid as f32;
-        if ui
-            .add(egui::Slider::new(&mut ppg, 1.0..=32.0).integer())
-            .changed()
-        {
-            self.canvas_state.pixels_per_grid = ppg as u32;
+    /// Drop tab `index` and its document, along with its recovery
+    /// snapshots. Closing the last tab leaves a blank one.
+    fn remove_tab(&mut self, index: usize) {
+        if self.tabs.len() == 1 {
+            self.stale_snapshots.push(self.document_id);
+            let mut tab = self.blank_tab();
+            self.swap_tab(&mut tab);
+            self.mark_clean();
+            return;
+        }
+        if index == self.active_tab {
+            self.switch_tab(if index + 1 < self.tabs.len() { index + 1 } else { index - 1 });
+        }
+        if let Some(tab) = self.tabs.remove(index) {
+            self.stale_snapshots.push(tab.document_id);
         }
+        if self.active_tab > index {
+            self.active_tab -= 1;
+        }
+    }
 
-        ui.add_space(12.0);
-        ui.separator();
+    /// Close every tab but the active one, asking first if any of them
+    /// have unsaved changes.
+    fn close_other_tabs(&mut self) {
+        let dirty = (0..self.tabs.len()).any(|i| i != self.active_tab && self.tab_info(i).1);
+        if dirty {
+            self.unsaved_prompt = Some(PendingClose::Others);
+        } else {
+            self.remove_other_tabs();
+        }
+    }
 
-        // View options
-        ui.label("View");
-        ui.checkbox(&mut self.canvas_state.show_grid, "Show Grid (G)");
-        ui.checkbox(&mut self.canvas_state.isometric, "Isometric (V)");
+    fn remove_other_tabs(&mut self) {
+        let closed = self.tabs.iter().flatten().map(|tab| tab.document_id);
+        self.stale_snapshots.extend(closed);
+        self.tabs.retain(Option::is_none);
+        self.active_tab = 0;
+    }
 
-        let mut zoom = self.canvas_state.zoom;
-        if ui
-            .add(egui::Slider::new(&mut zoom, 2.0..=128.0).text("Zoom").logarithmic(true))
-            .changed()
-        {
-            self.canvas_state.zoom = zoom;
+    /// Open a document in a new tab, or in the active one while that's
+    /// still the untouched sprite a new tab starts with.
+    fn open_animation(&mut self, animation: Animation) {
+        if self.source.is_some() || self.dirty {
+            self.new_tab();
         }
+        self.replace_animation(animation);
+    }
 
-        ui.add_space(12.0);
+    /// Swap in a new document, resetting the per-document view and block heights.
+    fn replace_animation(&mut self, animation: Animation) {
+        self.heights = HeightMap::new(animation.width(), animation.height());
+        self.tileset = Tileset::default();
+        self.selected_tile = 0;
+        self.pico8_cart = None;
+        self.palette = None;
+        self.palette_prompt = None;
+        self.png_palette = None;
+        self.png_text.clear();
+        self.animation = animation;
+        self.playing = false;
+        self.canvas_state.offset = egui::Vec2::ZERO;
+        self.canvas_state.show_footprint = false;
+        self.canvas_state.lock_footprint = false;
+        self.canvas_state.selection = None;
+        self.canvas_state.floating = None;
+        self.selection_drag = None;
+        self.source = None;
+        self.history = History::default();
+        // Loads and new documents mark themselves clean afterwards
+        self.dirty = true;
+    }
+
+    /// Record the document's changes since the last call as an undo step.
+    fn check_in_history(&mut self) {
+        self.history
+            .check_in(&self.animation, &self.heights, &self.tileset, &self.pico8_cart);
+    }
+
+    fn undo(&mut self) {
+        // Floating pixels aren't part of the frame yet, so undoing the paste
+        // just drops them
+        if self.canvas_state.floating.take().is_some() {
+            self.status_message = "Undid paste".into();
+            return;
+        }
+        self.check_in_history();
+        match self.history.undo() {
+            Some(state) => {
+                self.restore_history(state);
+                self.status_message = "Undo".into();
+            }
+            None => self.status_message = "Nothing to undo".into(),
+        }
+    }
+
+    fn redo(&mut self) {
+        self.drop_floating();
+        self.check_in_history();
+        match self.history.redo() {
+            Some(state) => {
+                self.restore_history(state);
+                self.status_message = "Redo".into();
+            }
+            None => self.status_message = "Nothing to redo".into(),
+        }
+    }
+
+    /// Put an undo step back, keeping the current frame and the export
+    /// settings.
+    fn restore_history(&mut self, state: history::State) {
+        let current = self.animation.current;
+        let export = std::mem::take(&mut self.animation.export);
+        self.animation = state.animation;
+        self.animation.current = current.min(self.animation.frames.len() - 1);
+        self.animation.export = export;
+        self.heights = state.heights;
+        self.tileset = state.tileset;
+        self.pico8_cart = state.pico8_cart;
+        self.canvas_state.selection = None;
+        self.selection_drag = None;
+        self.dirty = true;
+    }
+
+    /// Encode the current frame as a PNG, indexed when it fits in 256
+    /// colors, keeping the loaded palette order and text chunks. Also
+    /// returns a note on the encoding for the status bar.
+    fn encode_png(&mut self) -> Option<(Vec<u8>, String)> {
+        self.drop_floating();
+        let frame = &self.animation.frames[self.animation.current];
+        let metadata = io::png_file::Metadata {
+            grid: self.canvas_state.pixels_per_grid,
+            frame: self.animation.current,
+            frames: self.animation.frames.len(),
+            duration_ms: frame.duration_ms,
+            export: self.animation.export.clone(),
+        };
+        let palette = self.png_palette.as_deref().unwrap_or(&[]);
+        match io::png_file::encode(&frame.sprite, palette, &self.png_text, &metadata) {
+            Ok((png_data, indexed)) => {
+                let kind = match indexed {
+                    Some(colors) => format!(" (indexed, {colors} colors)"),
+                    None => String::new(),
+                };
+                Some((png_data, kind))
+            }
+            Err(e) => {
+                self.notifications.error(e);
+                None
+            }
+        }
+    }
+
+    /// PNG the document was opened from or last saved as, which Save
+    /// writes over.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_path(&self) -> Option<std::path::PathBuf> {
+        match &self.source {
+            Some(Source::File(path))
+                if path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("png")) =>
+            {
+                Some(path.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Name shown in the title and status bar.
+    fn document_name(&self) -> String {
+        source_name(self.source.as_ref())
+    }
+
+    /// Suggested file name for saving: the document's name as a PNG.
+    fn png_file_name(&self) -> String {
+        let name = self.source.as_ref().map(Source::label);
+        let stem = name
+            .as_deref()
+            .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem))
+            .filter(|stem| !stem.is_empty())
+            .unwrap_or("sprite");
+        format!("{stem}.png")
+    }
+
+    /// Save the current frame over the document's PNG, or ask where to as
+    /// Save As does when it has none. Returns whether the whole document
+    /// was saved, see `finish_save`.
+    fn save_file(&mut self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = self.save_path() {
+            let Some((png_data, kind)) = self.encode_png() else {
+                return false;
+            };
+            return match io::native::write_file(&path, &png_data) {
+                Ok(()) => {
+                    self.status_message = format!("Saved {}{kind}", self.document_name());
+                    self.finish_save()
+                }
+                Err(e) => {
+                    self.notifications.error(e);
+                    false
+                }
+            };
+        }
+        self.save_file_as()
+    }
+
+    /// Save the current frame as a new PNG, which natively becomes the
+    /// document's file. Browsers download it instead. Returns whether the
+    /// whole document was saved.
+    fn save_file_as(&mut self) -> bool {
+        let Some((png_data, kind)) = self.encode_png() else {
+            return false;
+        };
+        let file_name = self.png_file_name();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let Some(path) = io::native::pick_save_path(&file_name, PNG_FILTER) else {
+                self.status_message = "Save cancelled".into();
+                return false;
+            };
+            if let Err(e) = io::native::write_file(&path, &png_data) {
+                self.notifications.error(e);
+                return false;
+            }
+            let source = Source::File(path);
+            self.session.add_recent(source.clone());
+            self.source = Some(source);
+            self.status_message = format!("Saved {}{kind}", self.document_name());
+            self.finish_save()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(&png_data, &file_name, "image/png");
+            let ok = saved.is_ok() && self.finish_save();
+            self.report_download(saved, format!("Downloading {file_name}{kind}..."));
+            ok
+        }
+    }
+
+    /// What a PNG of the current frame leaves out of the document.
+    fn png_omissions(&self) -> Vec<&'static str> {
+        let mut lost = Vec::new();
+        if self.animation.frames.len() > 1 {
+            lost.push("the other frames");
+        }
+        if !self.animation.tags.is_empty() {
+            lost.push("tags");
+        }
+        if self.heights.max() > 0 {
+            lost.push("block heights");
+        }
+        if self.tileset.tiles.values().any(|tile| !tile.is_empty()) {
+            lost.push("tile properties");
+        }
+        if self.pico8_cart.is_some() {
+            lost.push("the PICO-8 cart data");
+        }
+        lost
+    }
+
+    /// Mark a written PNG as the saved document, unless it left part of the
+    /// document out: then the changes stay unsaved, with a warning saying
+    /// what's missing. Returns whether the whole document was saved, so
+    /// closing a tab or the window after a lossy save still waits.
+    fn finish_save(&mut self) -> bool {
+        let lost = self.png_omissions();
+        if lost.is_empty() {
+            self.mark_saved();
+            return true;
+        }
+        self.dirty = true;
+        self.notifications.error(format!(
+            "The PNG only holds the current frame, not {}. Export to a format that \
+             keeps them; until then the document stays unsaved.",
+            lost.join(", ")
+        ));
+        false
+    }
+
+    /// Save every tab with unsaved changes, the active one only with
+    /// `include_active`, then come back to the active tab. Stops on the
+    /// first save that fails, is cancelled or leaves data out, with its tab
+    /// showing.
+    fn save_dirty_tabs(&mut self, include_active: bool) -> bool {
+        let active = self.active_tab;
+        for index in 0..self.tabs.len() {
+            if (index == active && !include_active) || !self.tab_info(index).1 {
+                continue;
+            }
+            self.switch_tab(index);
+            if !self.save_file() {
+                return false;
+            }
+        }
+        self.switch_tab(active);
+        true
+    }
+
+    /// Show the outcome of a native save dialog: `done` in the status bar,
+    /// or the error as a notification.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn report_save(&mut self, result: Result<bool, io::Error>, done: String) {
+        match result {
+            Ok(true) => self.status_message = done,
+            Ok(false) => self.status_message = "Export cancelled".into(),
+            Err(e) => self.notifications.error(e),
+        }
+    }
+
+    /// Like `report_save` for browser downloads, which can't be cancelled
+    /// from here.
+    #[cfg(target_arch = "wasm32")]
+    fn report_download(&mut self, result: Result<(), io::Error>, message: String) {
+        match result {
+            Ok(()) => self.status_message = message,
+            Err(e) => self.notifications.error(e),
+        }
+    }
+
+    /// The open document as a recovery snapshot.
+    fn recovery_document(&self) -> recovery::Document {
+        recovery::Document {
+            animation: self.animation.clone(),
+            heights: self.heights.clone(),
+            tileset: self.tileset.clone(),
+            palette: self.palette.clone(),
+            png_palette: self.png_palette.clone(),
+            png_text: self.png_text.clone(),
+            pixels_per_grid: self.canvas_state.pixels_per_grid,
+            source: self.source.clone(),
+        }
+    }
+
+    /// Note that the document as it is now needs no snapshot, having just
+    /// been loaded, created or saved.
+    fn mark_clean(&mut self) {
+        self.dirty = false;
+        if let Ok(json) = self.recovery_document().to_json() {
+            self.autosave_fingerprint = recovery::fingerprint(&json);
+        }
+    }
+
+    /// Record an explicit save. The document's snapshots aren't needed any
+    /// more, nor offered on startup.
+    fn mark_saved(&mut self) {
+        self.stale_snapshots.push(self.document_id);
+        self.mark_clean();
+    }
+
+    /// Carry out a pending restore or discard, drop the snapshots of saved
+    /// and closed documents, then snapshot the documents once per autosave
+    /// interval.
+    fn autosave(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        match self.recovery_request.take() {
+            Some(RecoveryRequest::Restore(snapshots)) => {
+                for snapshot in snapshots {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let document = recovery::native::read(snapshot);
+                    #[cfg(target_arch = "wasm32")]
+                    let document = match frame.storage() {
+                        Some(storage) => recovery::web::read(storage, snapshot),
+                        None => Err(io::Error::Browser("no storage".into())),
+                    };
+                    match document {
+                        Ok(document) => self.restore_snapshot(document, snapshot),
+                        Err(e) => self.notifications.error(e),
+                    }
+                }
+            }
+            Some(RecoveryRequest::DiscardAll) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Err(e) = recovery::native::clear(None) {
+                    self.notifications.error(e);
+                }
+                #[cfg(target_arch = "wasm32")]
+                if let Some(storage) = frame.storage_mut() {
+                    recovery::web::clear(storage, None);
+                }
+                self.snapshots.clear();
+                self.status_message = "Recovery snapshots deleted".into();
+            }
+            None => {}
+        }
+        self.forget_stale_snapshots(frame.storage_mut().map(|s| s as &mut dyn eframe::Storage));
+
+        let interval = self.session.autosave.interval_secs;
+        if interval == 0 {
+            return;
+        }
+        let time = ctx.input(|i| i.time);
+        if time < self.next_autosave {
+            // Wake up for the snapshot even if the user stops interacting
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(
+                self.next_autosave - time,
+            ));
+            return;
+        }
+        self.next_autosave = time + f64::from(interval);
+        self.write_snapshots(frame.storage_mut().map(|s| s as &mut dyn eframe::Storage));
+    }
+
+    /// Delete the snapshots of documents saved or closed since last time.
+    fn forget_stale_snapshots(&mut self, storage: Option<&mut dyn eframe::Storage>) {
+        if self.stale_snapshots.is_empty() {
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = storage;
+            for document in self.stale_snapshots.drain(..) {
+                if let Err(e) = recovery::native::clear(Some(document)) {
+                    self.notifications.error(e);
+                }
+            }
+            self.snapshots = recovery::native::list();
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some(storage) = storage {
+            for document in self.stale_snapshots.drain(..) {
+                recovery::web::clear(storage, Some(document));
+            }
+            self.snapshots = recovery::web::list(storage);
+        }
+    }
+
+    /// Snapshot every tab's document that has unsaved changes, unless it's
+    /// unchanged since it was last snapshotted. Each document is keyed by
+    /// its tab, so it keeps its own newest snapshots.
+    fn write_snapshots(&mut self, storage: Option<&mut dyn eframe::Storage>) {
+        #[cfg(target_arch = "wasm32")]
+        let mut storage = storage;
+        let (time, keep) = (recovery::now(), self.session.autosave.keep);
+        for index in 0..self.tabs.len() {
+            let (document, document_id, last) = match &self.tabs[index] {
+                Some(tab) if tab.dirty => {
+                    (tab.recovery_document(), tab.document_id, tab.autosave_fingerprint)
+                }
+                None if self.dirty => {
+                    (self.recovery_document(), self.document_id, self.autosave_fingerprint)
+                }
+                _ => continue,
+            };
+            let json = match document.to_json() {
+                Ok(json) => json,
+                Err(e) => {
+                    self.notifications.error(io::Error::Encode(e));
+                    continue;
+                }
+            };
+            let fingerprint = recovery::fingerprint(&json);
+            if fingerprint == last {
+                continue;
+            }
+            // A failed write isn't retried until the document changes again
+            match &mut self.tabs[index] {
+                Some(tab) => tab.autosave_fingerprint = fingerprint,
+                None => self.autosave_fingerprint = fingerprint,
+            }
+            let snapshot = recovery::Snapshot {
+                time,
+                document: document_id,
+            };
+            // Snapshots are plain files natively
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Err(e) = recovery::native::write(snapshot, &json, keep) {
+                self.notifications.error(e);
+            }
+            #[cfg(target_arch = "wasm32")]
+            if let Some(storage) = storage.as_deref_mut() {
+                recovery::web::write(storage, snapshot, &json, keep);
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = storage;
+            self.snapshots = recovery::native::list();
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some(storage) = storage {
+            self.snapshots = recovery::web::list(storage);
+        }
+    }
+
+    fn restore_snapshot(&mut self, document: recovery::Document, snapshot: recovery::Snapshot) {
+        self.open_animation(document.animation);
+        // Carry on with the snapshot's own document unless a tab has it open
+        let open = self.tabs.iter().flatten().any(|tab| tab.document_id == snapshot.document);
+        if !open {
+            self.document_id = snapshot.document;
+        }
+        self.heights = document.heights;
+        self.tileset = document.tileset;
+        self.palette = document.palette;
+        self.png_palette = document.png_palette;
+        self.png_text = document.png_text;
+        self.canvas_state.pixels_per_grid = document.pixels_per_grid.clamp(1, 32);
+        self.source = document.source;
+        // Identical to the snapshot, so nothing new to write yet, but
+        // still unsaved
+        self.mark_clean();
+        self.dirty = true;
+        self.status_message =
+            format!("Restored snapshot from {}", recovery::age(snapshot.time));
+    }
+
+    /// Save the current frame with the document's export settings.
+    fn export_scaled(&mut self) {
+        let settings = &self.animation.export;
+        let Some(image) = export::render(
+            self.animation.current(),
+            settings,
+            self.canvas_state.pixels_per_grid,
+        ) else {
+            self.notifications.error("Export image is too large");
+            return;
+        };
+        let png_data = match io::sprite_to_png(&image) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        let file_name = format!("sprite@{}x.png", settings.scale);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let saved = io::native::save_file_dialog(&png_data, &file_name, PNG_FILTER);
+            self.report_save(saved, format!("Exported {}x{} PNG", image.width, image.height));
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(&png_data, &file_name, "image/png");
+            self.report_download(saved, format!("Downloading {}...", file_name));
+        }
+    }
+
+    fn export_iso_block(&mut self) {
+        let block = iso::render_blocks(self.animation.current(), &self.heights);
+        let png_data = match io::sprite_to_png(&block) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let saved = io::native::save_file_dialog(&png_data, "block.png", PNG_FILTER);
+            let done = format!("Exported {}x{} isometric block", block.width, block.height);
+            self.report_save(saved, done);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(&png_data, "block.png", "image/png");
+            self.report_download(saved, "Downloading isometric block...".into());
+        }
+    }
+
+    fn export_gif(&mut self) {
+        let data = match io::gif::write(&self.animation, &self.gif_options) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let saved = io::native::save_file_dialog(&data, "sprite.gif", ("GIF Image", &["gif"]));
+            let done = format!("Exported GIF with {} frame(s)", self.animation.frames.len());
+            self.report_save(saved, done);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(&data, "sprite.gif", "image/gif");
+            self.report_download(saved, "Downloading GIF...".into());
+        }
+    }
+
+    fn export_apng(&mut self) {
+        let data = match io::animation_to_apng(&self.animation) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = ("Animated PNG", &["png", "apng"][..]);
+            let saved = io::native::save_file_dialog(&data, "sprite.png", filter);
+            let done = format!("Exported APNG with {} frame(s)", self.animation.frames.len());
+            self.report_save(saved, done);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(&data, "sprite.png", "image/apng");
+            self.report_download(saved, "Downloading APNG...".into());
+        }
+    }
+
+    fn export_webp(&mut self) {
+        let data = match io::webp::write(&self.animation) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = ("WebP Image", &["webp"][..]);
+            let saved = io::native::save_file_dialog(&data, "sprite.webp", filter);
+            let done = format!("Exported WebP with {} frame(s)", self.animation.frames.len());
+            self.report_save(saved, done);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(&data, "sprite.webp", "image/webp");
+            self.report_download(saved, "Downloading WebP...".into());
+        }
+    }
+
+    /// Save the sprite sheet image with its JSON atlas alongside.
+    fn export_sheet(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let Some(path) = io::native::pick_save_path("sheet.png", PNG_FILTER) else {
+                self.status_message = "Export cancelled".into();
+                return;
+            };
+            let image_name = path
+                .file_name()
+                .map_or("sheet.png".into(), |n| n.to_string_lossy().into_owned());
+            let sheet = io::sheet::build(&self.animation, &self.sheet_options, &image_name);
+            let written = io::sprite_to_png(&sheet.image)
+                .and_then(|png| io::native::write_file(&path, &png))
+                .and_then(|()| {
+                    io::native::write_file(&path.with_extension("json"), sheet.json.as_bytes())
+                });
+            let done = format!(
+                "Exported {}x{} sprite sheet with {} frame(s)",
+                sheet.image.width,
+                sheet.image.height,
+                self.animation.frames.len()
+            );
+            self.report_save(written.map(|()| true), done);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let sheet = io::sheet::build(&self.animation, &self.sheet_options, "sheet.png");
+            let saved = io::sprite_to_png(&sheet.image)
+                .and_then(|png| io::web::save_file(&png, "sheet.png", "image/png"))
+                .and_then(|()| {
+                    io::web::save_file(sheet.json.as_bytes(), "sheet.json", "application/json")
+                });
+            self.report_download(saved, "Downloading sprite sheet...".into());
+        }
+    }
+
+    /// Save a Tiled tileset: the tile image, the .tsx next to it and
+    /// optionally a .tmx map laying the tiles out like the sprite.
+    fn export_tiled(&mut self) {
+        let tile_size = self.canvas_state.pixels_per_grid;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let Some(path) =
+                io::native::pick_save_path("tileset.tsx", ("Tiled Tileset", &["tsx"]))
+            else {
+                self.status_message = "Export cancelled".into();
+                return;
+            };
+            let name = path
+                .file_stem()
+                .map_or("tileset".into(), |n| n.to_string_lossy().into_owned());
+            let export = match io::tiled::export(
+                &self.animation,
+                tile_size,
+                &self.tileset,
+                &self.tiled_options,
+                &name,
+            ) {
+                Ok(export) => export,
+                Err(e) => {
+                    self.notifications.error(e);
+                    return;
+                }
+            };
+            let mut written = io::native::write_file(&path, export.tsx.as_bytes())
+                .and_then(|()| io::sprite_to_png(&export.image))
+                .and_then(|png| io::native::write_file(&path.with_extension("png"), &png));
+            if let Some(tmx) = &export.tmx {
+                written = written.and_then(|()| {
+                    io::native::write_file(&path.with_extension("tmx"), tmx.as_bytes())
+                });
+            }
+            self.report_save(
+                written.map(|()| true),
+                format!("Exported Tiled tileset {name}.tsx"),
+            );
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let export = match io::tiled::export(
+                &self.animation,
+                tile_size,
+                &self.tileset,
+                &self.tiled_options,
+                "tileset",
+            ) {
+                Ok(export) => export,
+                Err(e) => {
+                    self.notifications.error(e);
+                    return;
+                }
+            };
+            let mut saved =
+                io::web::save_file(export.tsx.as_bytes(), "tileset.tsx", "application/xml")
+                    .and_then(|()| io::sprite_to_png(&export.image))
+                    .and_then(|png| io::web::save_file(&png, "tileset.png", "image/png"));
+            if let Some(tmx) = &export.tmx {
+                saved = saved.and_then(|()| {
+                    io::web::save_file(tmx.as_bytes(), "tileset.tmx", "application/xml")
+                });
+            }
+            self.report_download(saved, "Downloading Tiled tileset...".into());
+        }
+    }
+
+    /// Save a Godot SpriteFrames resource and the sheet it points at, laid
+    /// out with the sprite sheet export settings.
+    fn export_godot(&mut self) {
+        let (image, placements) = io::sheet::render(&self.animation, &self.sheet_options);
+        let png_data = match io::sprite_to_png(&image) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        let animations = self.animation.tags_or_default().len();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = ("Godot Resource", &["tres"][..]);
+            let Some(path) = io::native::pick_save_path("sprite_frames.tres", filter) else {
+                self.status_message = "Export cancelled".into();
+                return;
+            };
+            let image_path = path.with_extension("png");
+            let image_name = image_path
+                .file_name()
+                .map_or("sprite_frames.png".into(), |n| n.to_string_lossy().into_owned());
+            let tres = io::godot::sprite_frames(&self.animation, &placements, &image_name);
+            let written = io::native::write_file(&path, tres.as_bytes())
+                .and_then(|()| io::native::write_file(&image_path, &png_data));
+            self.report_save(
+                written.map(|()| true),
+                format!("Exported SpriteFrames with {animations} animation(s)"),
+            );
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let tres =
+                io::godot::sprite_frames(&self.animation, &placements, "sprite_frames.png");
+            let saved = io::web::save_file(tres.as_bytes(), "sprite_frames.tres", "text/plain")
+                .and_then(|()| io::web::save_file(&png_data, "sprite_frames.png", "image/png"));
+            self.report_download(
+                saved,
+                format!("Downloading SpriteFrames with {animations} animation(s)..."),
+            );
+        }
+    }
+
+    fn export_code(&mut self) {
+        let export = match io::code::export(&self.animation, &self.code_options) {
+            Ok(export) => export,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        let output = self.code_options.output;
+        let file_name = format!(
+            "{}.{}",
+            io::code::identifier(&self.code_options.name),
+            output.extension()
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = (output.name(), &[output.extension()][..]);
+            let Some(path) = io::native::pick_save_path(&file_name, filter) else {
+                self.status_message = "Export cancelled".into();
+                return;
+            };
+            let mut written = io::native::write_file(&path, &export.data);
+            if let Some(palette) = &export.palette {
+                written = written
+                    .and_then(|()| io::native::write_file(&path.with_extension("pal"), palette));
+            }
+            let done = format!(
+                "Exported {} as {}",
+                self.code_options.format.name(),
+                output.name()
+            );
+            self.report_save(written.map(|()| true), done);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let mime = match output {
+                CodeOutput::RawBinary => "application/octet-stream",
+                _ => "text/plain",
+            };
+            let mut saved = io::web::save_file(&export.data, &file_name, mime);
+            if let Some(palette) = &export.palette {
+                let palette_name = format!("{}.pal", io::code::identifier(&self.code_options.name));
+                saved = saved.and_then(|()| {
+                    io::web::save_file(palette, &palette_name, "application/octet-stream")
+                });
+            }
+            self.report_download(saved, format!("Downloading {}...", file_name));
+        }
+    }
+
+    fn export_tiles(&mut self) {
+        let format = self.retro_format;
+        let export = match retro::export(
+            self.animation.current(),
+            format,
+            &self.retro_palette,
+            self.retro_dedup,
+        ) {
+            Ok(export) => export,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        let file_name = format!("tiles.{}", format.extension());
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = (format.name(), &[format.extension()][..]);
+            let Some(path) = io::native::pick_save_path(&file_name, filter) else {
+                self.status_message = "Export cancelled".into();
+                return;
+            };
+            let mut written = io::native::write_file(&path, &export.data);
+            if let Some(map) = &export.map {
+                written =
+                    written.and_then(|()| io::native::write_file(&path.with_extension("map"), map));
+            }
+            self.report_save(
+                written.map(|()| true),
+                format!("Exported {} {} tile(s)", export.tile_count, format.name()),
+            );
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let binary = "application/octet-stream";
+            let mut saved = io::web::save_file(&export.data, &file_name, binary);
+            if let Some(map) = &export.map {
+                saved = saved.and_then(|()| io::web::save_file(map, "tiles.map", binary));
+            }
+            self.report_download(saved, format!("Downloading {} tile(s)...", export.tile_count));
+        }
+    }
+
+    /// Write the sprite into the loaded cart, or a new empty one, leaving
+    /// its code, map and sound untouched.
+    fn export_pico8(&mut self) {
+        let cart = self.pico8_cart.clone().unwrap_or_default();
+        let text = match pico8::write(&cart, self.animation.current()) {
+            Ok(text) => text,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = ("PICO-8 Cartridge", &["p8"][..]);
+            let saved = io::native::save_file_dialog(text.as_bytes(), "cart.p8", filter);
+            self.report_save(saved, "Exported PICO-8 cart".into());
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(text.as_bytes(), "cart.p8", "text/plain");
+            self.report_download(saved, "Downloading PICO-8 cart...".into());
+        }
+    }
+
+    fn export_image(&mut self) {
+        let format = self.image_options.format;
+        let data = match raster::encode(self.animation.current(), &self.image_options) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        let file_name = format!("sprite.{}", format.extension());
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = (format.name(), &[format.extension()][..]);
+            let saved = io::native::save_file_dialog(&data, &file_name, filter);
+            self.report_save(saved, format!("Exported {}", format.name()));
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let mime = match format {
+                ImageFormat::Bmp => "image/bmp",
+                ImageFormat::Tga => "image/x-tga",
+                ImageFormat::Qoi => "image/qoi",
+                ImageFormat::Ico | ImageFormat::Cur => "image/vnd.microsoft.icon",
+            };
+            let saved = io::web::save_file(&data, &file_name, mime);
+            self.report_download(saved, format!("Downloading {}...", file_name));
+        }
+    }
+
+    fn export_aseprite(&mut self) {
+        let data = match io::aseprite::write(&self.animation) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let filter = ("Aseprite File", &["aseprite", "ase"][..]);
+            let saved = io::native::save_file_dialog(&data, "sprite.aseprite", filter);
+            self.report_save(saved, "Exported Aseprite file".into());
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(&data, "sprite.aseprite", "application/octet-stream");
+            self.report_download(saved, "Downloading Aseprite file...".into());
+        }
+    }
+
+    /// Replace the flat frames with their baked isometric projection, as
+    /// one undoable step.
+    fn convert_to_isometric(&mut self) {
+        self.drop_floating();
+        let (w, h) = (self.animation.width(), self.animation.height());
+        for frame in &mut self.animation.frames {
+            frame.sprite = iso::project_flat(&frame.sprite);
+        }
+        // Block heights were painted on the flat layout and don't carry over
+        self.heights = HeightMap::new(self.animation.width(), self.animation.height());
+        self.status_message = format!(
+            "Converted {}x{} sprite to {}x{} isometric tile",
+            w,
+            h,
+            self.animation.width(),
+            self.animation.height()
+        );
+        self.canvas_state.selection = None;
+        self.selection_drag = None;
+        self.canvas_state.isometric = false;
+        self.canvas_state.show_footprint = w == h;
+        self.dirty = true;
+    }
+
+    /// Save the sprite as an isometric tile, clearing everything outside the
+    /// diamond footprint.
+    fn export_iso_tile(&mut self) {
+        let tile = iso::mask_to_footprint(self.animation.current());
+        let png_data = match io::sprite_to_png(&tile) {
+            Ok(data) => data,
+            Err(e) => {
+                self.notifications.error(e);
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let saved = io::native::save_file_dialog(&png_data, "tile.png", PNG_FILTER);
+            let done = format!("Exported {}x{} isometric tile", tile.width, tile.height);
+            self.report_save(saved, done);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let saved = io::web::save_file(&png_data, "tile.png", "image/png");
+            self.report_download(saved, "Downloading isometric tile...".into());
+        }
+    }
+
+    fn check_pending_file(&mut self) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            match io::web::check_pending_file() {
+                Some(Ok(data)) => {
+                    if std::mem::take(&mut self.pending_tile_import) {
+                        self.load_tiles(&data);
+                    } else if let Some(url) = self.pending_url.take() {
+                        self.load_from_source(&data, Source::Url(url));
+                    } else {
+                        self.load_bytes(&data, "");
+                    }
+                }
+                Some(Err(e)) => {
+                    self.pending_tile_import = false;
+                    self.pending_url = None;
+                    self.restore_view = None;
+                    self.notifications.error(e);
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn apply_tool_at(&mut self, x: u32, y: u32) {
+        let color_arr = [
+            self.primary_color.r(),
+            self.primary_color.g(),
+            self.primary_color.b(),
+            self.primary_color.a(),
+        ];
+
+        let (w, h) = (self.animation.width(), self.animation.height());
+        let locked = self.canvas_state.lock_footprint;
+        let paints = !matches!(self.current_tool, Tool::ColorPicker);
+        if locked && paints && !iso::diamond_contains(w, h, x, y) {
+            return;
+        }
+        if paints {
+            self.dirty = true;
+        }
+
+        match self.current_tool {
+            Tool::Pencil => {
+                self.animation.current_mut().set_pixel(x, y, color_arr);
+            }
+            Tool::Eraser => {
+                self.animation.current_mut().set_pixel(x, y, [0, 0, 0, 0]);
+            }
+            Tool::Fill if locked => {
+                self.animation.current_mut().flood_fill_within(x, y, color_arr, |fx, fy| {
+                    iso::diamond_contains(w, h, fx, fy)
+                });
+            }
+            Tool::Fill => {
+                self.animation.current_mut().flood_fill(x, y, color_arr);
+            }
+            Tool::ColorPicker => {
+                let [r, g, b, a] = self.animation.current().get_pixel(x, y);
+                self.primary_color = Color32::from_rgba_unmultiplied(r, g, b, a);
+                self.current_tool = Tool::Pencil;
+            }
+            Tool::Raise | Tool::Lower => {
+                // Each cell changes once per stroke, however long the drag lingers
+                if self.height_stroke.contains(&(x, y)) {
+                    return;
+                }
+                self.height_stroke.push((x, y));
+                if self.current_tool == Tool::Raise {
+                    self.heights.raise(x, y, self.height_step);
+                } else {
+                    self.heights.lower(x, y, self.height_step);
+                }
+            }
+            // Drags go to `drag_selection` instead
+            Tool::Select => {}
+        }
+    }
+
+    fn handle_canvas_response(&mut self, response: canvas::CanvasResponse) {
+        // Update status with hover position
+        if let Some((x, y)) = response.hovered_pixel {
+            let [r, g, b, a] = self.animation.current().get_pixel(x, y);
+            self.status_message = format!(
+                "({}, {})  RGBA({}, {}, {}, {})",
+                x, y, r, g, b, a
+            );
+            if self.canvas_state.isometric && self.canvas_state.blocks {
+                self.status_message += &format!("  Height {}", self.heights.get(x, y));
+            }
+        }
+
+        // Handle painting with line interpolation. Interpolation runs in
+        // canvas space so strokes crossing a tiled copy's edge wrap cleanly.
+        if self.current_tool == Tool::Select {
+            self.drag_selection(response.painted_pixels.last().copied());
+        } else if !response.painted_pixels.is_empty() {
+            for &(x, y) in &response.painted_pixels {
+                // Interpolate from last position for smooth lines
+                let line = match self.last_paint_pos {
+                    Some((lx, ly)) => tools::line_pixels(lx, ly, x, y),
+                    None => vec![(x, y)],
+                };
+                for (cx, cy) in line {
+                    if let Some((px, py)) =
+                        self.canvas_state.wrap_pixel(cx, cy, self.animation.current())
+                    {
+                        self.apply_tool_at(px, py);
+                    }
+                }
+                self.last_paint_pos = Some((x, y));
+            }
+        } else {
+            self.last_paint_pos = None;
+            self.height_stroke.clear();
+        }
+
+        // Handle right-click color pick
+        if let Some([r, g, b, a]) = response.picked_color {
+            self.primary_color = Color32::from_rgba_unmultiplied(r, g, b, a);
+            self.status_message = format!("Picked RGBA({}, {}, {}, {})", r, g, b, a);
+        }
+    }
+
+    /// Select tool: drag out a selection, or drag the floating pixels
+    /// around. `pos` is the canvas pixel under the pointer while the button
+    /// is held.
+    fn drag_selection(&mut self, pos: Option<(i32, i32)>) {
+        let Some((x, y)) = pos else {
+            self.selection_drag = None;
+            return;
+        };
+        let (w, h) = (self.animation.width(), self.animation.height());
+        let corner = (x.clamp(0, w as i32 - 1) as u32, y.clamp(0, h as i32 - 1) as u32);
+        match self.selection_drag {
+            None => {
+                let on_floating = self
+                    .canvas_state
+                    .floating
+                    .as_ref()
+                    .is_some_and(|f| f.contains(x, y));
+                if on_floating {
+                    self.selection_drag = Some(selection::Drag::Move((x, y)));
+                } else {
+                    // Clicking away drops what was floating
+                    self.drop_floating();
+                    self.selection_drag = Some(selection::Drag::Marquee(corner));
+                    self.canvas_state.selection = Some(selection::bounds(corner, corner));
+                }
+            }
+            Some(selection::Drag::Marquee(anchor)) => {
+                self.canvas_state.selection = Some(selection::bounds(anchor, corner));
+            }
+            Some(selection::Drag::Move((last_x, last_y))) => {
+                if let Some(floating) = &mut self.canvas_state.floating {
+                    floating.x += x - last_x;
+                    floating.y += y - last_y;
+                }
+                self.selection_drag = Some(selection::Drag::Move((x, y)));
+            }
+        }
+    }
+
+    /// Composite the floating pixels onto the current frame.
+    fn drop_floating(&mut self) {
+        if let Some(floating) = self.canvas_state.floating.take() {
+            floating.stamp(self.animation.current_mut());
+            self.dirty = true;
+        }
+    }
+
+    /// Copy the floating pixels, else the selection, else the whole frame.
+    fn copy_to_clipboard(&mut self) {
+        let image = if let Some(floating) = &self.canvas_state.floating {
+            floating.sprite.clone()
+        } else if let Some(rect) = self.canvas_state.selection {
+            self.animation.current().crop(rect)
+        } else {
+            self.animation.current().clone()
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let copied = self.clipboard.copy(&image);
+        #[cfg(target_arch = "wasm32")]
+        let copied = io::clipboard::web::copy(&image);
+        match copied {
+            Ok(()) => {
+                self.status_message = format!("Copied {}x{} pixels", image.width, image.height)
+            }
+            Err(e) => self.notifications.error(e),
+        }
+    }
+
+    /// Paste the clipboard image as a floating selection, or as a new
+    /// sprite with `as_sprite`.
+    fn paste_from_clipboard(&mut self, as_sprite: bool) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            match self.clipboard.paste() {
+                Ok(image) => self.place_pasted(image, as_sprite),
+                Err(e) => self.notifications.error(e),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.pending_paste_as_sprite = as_sprite;
+            io::clipboard::web::request_paste();
+            self.status_message = "Pasting...".into();
+        }
+    }
+
+    fn place_pasted(&mut self, image: Sprite, as_sprite: bool) {
+        if as_sprite {
+            self.status_message = format!("Pasted {}x{} sprite", image.width, image.height);
+            self.open_sprite(image);
+            return;
+        }
+        self.drop_floating();
+        // Land on the selection if there is one, so copy and paste between
+        // frames keeps the position
+        let (x, y) = self
+            .canvas_state
+            .selection
+            .map_or((0, 0), |[x, y, _, _]| (x as i32, y as i32));
+        self.status_message = format!(
+            "Pasted {}x{} pixels, drag to move, click outside or press Esc to drop",
+            image.width, image.height
+        );
+        self.canvas_state.floating = Some(Floating {
+            sprite: image,
+            x,
+            y,
+        });
+        self.canvas_state.selection = None;
+        self.current_tool = Tool::Select;
+        self.dirty = true;
+    }
+
+    fn check_pending_paste(&mut self) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            match io::clipboard::web::check_pending() {
+                Some(Ok(image)) => {
+                    let as_sprite = std::mem::take(&mut self.pending_paste_as_sprite);
+                    self.place_pasted(image, as_sprite);
+                }
+                Some(Err(e)) => self.notifications.error(e),
+                None => {}
+            }
+        }
+    }
+
+    fn toggle_playback(&mut self) {
+        self.playing = !self.playing;
+        self.play_elapsed_ms = 0.0;
+    }
+
+    /// Advance the preview by the time since the last frame. The preview
+    /// always loops; the loop count only affects exports.
+    fn advance_playback(&mut self, ctx: &egui::Context) {
+        if !self.playing || self.animation.frames.len() < 2 {
+            return;
+        }
+        self.play_elapsed_ms += ctx.input(|i| i.stable_dt) * 1000.0;
+        loop {
+            let duration = self.animation.frames[self.animation.current].duration_ms.max(1) as f32;
+            if self.play_elapsed_ms < duration {
+                break;
+            }
+            self.play_elapsed_ms -= duration;
+            self.animation.next_frame();
+        }
+        ctx.request_repaint();
+    }
+
+    fn show_timeline(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Frames");
+            ui.separator();
+
+            let play_label = if self.playing { "\u{23F9} Stop" } else { "\u{25B6} Play" };
+            if ui.button(play_label).on_hover_text("Enter").clicked() {
+                self.toggle_playback();
+            }
+            let mut changed = false;
+            if ui.button("+").on_hover_text("Add frame").clicked() {
+                self.animation.add_frame();
+                changed = true;
+            }
+            if ui.button("Duplicate").clicked() {
+                self.animation.duplicate_frame();
+                changed = true;
+            }
+            if ui
+                .add_enabled(self.animation.frames.len() > 1, egui::Button::new("Delete"))
+                .clicked()
+            {
+                self.animation.remove_frame();
+                changed = true;
+            }
+            if ui.button("\u{25C0}").on_hover_text("Move frame earlier").clicked() {
+                self.animation.move_frame(-1);
+                changed = true;
+            }
+            if ui.button("\u{25B6}").on_hover_text("Move frame later").clicked() {
+                self.animation.move_frame(1);
+                changed = true;
+            }
+            ui.separator();
+
+            ui.label("Duration");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.animation.current_frame_mut().duration_ms)
+                        .range(10..=10_000)
+                        .suffix(" ms"),
+                )
+                .changed();
+            ui.label("Loops");
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.animation.loop_count).range(0..=1000))
+                .on_hover_text("Times to play exported animations, 0 = forever")
+                .changed();
+            self.dirty |= changed;
+        });
+
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for index in 0..self.animation.frames.len() {
+                    let selected = index == self.animation.current;
+                    let duration = self.animation.frames[index].duration_ms;
+                    if ui
+                        .selectable_label(selected, format!("{}", index + 1))
+                        .on_hover_text(format!("{duration} ms"))
+                        .clicked()
+                    {
+                        self.animation.current = index;
+                        self.playing = false;
+                    }
+                }
+            });
+        });
+
+        self.show_tags(ui);
+    }
+
+    /// Named frame ranges, exported as separate animations.
+    fn show_tags(&mut self, ui: &mut egui::Ui) {
+        let last = self.animation.frames.len() - 1;
+        let mut remove = None;
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Tags");
+            for (i, tag) in self.animation.tags.iter_mut().enumerate() {
+                ui.separator();
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut tag.name).desired_width(70.0))
+                    .changed();
+                changed |= ui.add(frame_number(&mut tag.from, last)).changed();
+                ui.label("-");
+                changed |= ui.add(frame_number(&mut tag.to, last)).changed();
+                tag.to = tag.to.max(tag.from);
+                changed |= ui.checkbox(&mut tag.looping, "Loop").changed();
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+            }
+            ui.separator();
+            if ui.button("+ Tag").on_hover_text("Tag the current frame").clicked() {
+                let current = self.animation.current;
+                self.animation.tags.push(Tag {
+                    name: format!("tag{}", self.animation.tags.len() + 1),
+                    from: current,
+                    to: current,
+                    looping: true,
+                });
+                changed = true;
+            }
+        });
+        if let Some(i) = remove {
+            self.animation.tags.remove(i);
+            changed = true;
+        }
+        self.dirty |= changed;
+    }
+
+    /// One tab per open document, with a close button and a context menu.
+    fn show_tabs(&mut self, ui: &mut egui::Ui) {
+        let mut switch = None;
+        let mut close = None;
+        let mut close_others = false;
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for index in 0..self.tabs.len() {
+                    let (name, dirty) = self.tab_info(index);
+                    let marker = if dirty { "*" } else { "" };
+                    let label =
+                        ui.selectable_label(index == self.active_tab, format!("{name}{marker}"));
+                    if label.clicked() {
+                        switch = Some(index);
+                    }
+                    label.context_menu(|ui| {
+                        if ui.button("Close").clicked() {
+                            close = Some(index);
+                            ui.close_menu();
+                        }
+                        if ui.button("Close Others").clicked() {
+                            switch = Some(index);
+                            close_others = true;
+                            ui.close_menu();
+                        }
+                    });
+                    if ui.small_button("x").on_hover_text("Close tab").clicked() {
+                        close = Some(index);
+                    }
+                    ui.separator();
+                }
+                if ui.small_button("+").on_hover_text("New tab").clicked() {
+                    self.new_tab();
+                }
+            });
+        });
+        if let Some(index) = switch {
+            self.switch_tab(index);
+        }
+        if close_others {
+            self.close_other_tabs();
+        }
+        if let Some(index) = close {
+            self.close_tab(index);
+        }
+    }
+
+    fn show_tool_panel(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.heading("Tools");
+            ui.separator();
+
+            let tools = [
+                Tool::Pencil,
+                Tool::Eraser,
+                Tool::Fill,
+                Tool::ColorPicker,
+                Tool::Raise,
+                Tool::Lower,
+                Tool::Select,
+            ];
+            for tool in tools {
+                let selected = self.current_tool == tool;
+                let text = format!("{} {}", tool.icon(), tool.shortcut());
+                if ui
+                    .selectable_label(selected, &text)
+                    .on_hover_text(tool.name())
+                    .clicked()
+                {
+                    self.current_tool = tool;
+                }
+            }
+        });
+    }
+
+    fn show_properties_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Properties");
+        ui.separator();
+
+        // Color picker
+        ui.label("Color");
+        ui.color_edit_button_srgba(&mut self.primary_color);
+        ui.add_space(8.0);
+
+        let mut clear_palette = false;
+        if let Some(colors) = &self.palette {
+            ui.horizontal(|ui| {
+                ui.label("Palette");
+                clear_palette = ui.small_button("Clear").clicked();
+            });
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+                for (i, &[r, g, b, a]) in colors.iter().enumerate() {
+                    let color = Color32::from_rgba_unmultiplied(r, g, b, a);
+                    let swatch = egui::Button::new("")
+                        .fill(color)
+                        .min_size(egui::vec2(14.0, 14.0));
+                    if ui.add(swatch).on_hover_text(format!("Index {i}")).clicked() {
+                        self.primary_color = color;
+                    }
+                }
+            });
+            ui.add_space(8.0);
+        }
+        if clear_palette {
+            self.palette = None;
+            self.dirty = true;
+        }
+
+        // Alpha slider
+        let mut alpha = self.primary_color.a() as f32 / 255.0;
+        if ui
+            .add(egui::Slider::new(&mut alpha, 0.0..=1.0).text("Alpha"))
+            .changed()
+        {
+            let [r, g, b, _] = self.primary_color.to_array();
+            self.primary_color =
+                Color32::from_rgba_unmultiplied(r, g, b, (alpha * 255.0) as u8);
+        }
+
+        ui.add_space(12.0);
+        ui.separator();
+
+        // Sprite info
+        ui.label("Sprite");
+        ui.label(format!(
+            "Size: {} x {}",
+            self.animation.width(), self.animation.height()
+        ));
+        ui.add_space(4.0);
+
+        // Pixels per grid
+        ui.label("Pixels per grid box");
+        let mut ppg = self.canvas_state.pixels_per_grid as f32;
+        if ui
+            .add(egui::Slider::new(&mut ppg, 1.0..=32.0).integer())
+            .changed()
+        {
+            self.canvas_state.pixels_per_grid = ppg as u32;
+        }
+
+        if self.canvas_state.pixels_per_grid > 1 {
+            ui.add_space(12.0);
+            ui.separator();
+            self.show_tile_section(ui);
+        }
+
+        ui.add_space(12.0);
+        ui.separator();
+
+        // View options
+        ui.label("View");
+        ui.checkbox(&mut self.canvas_state.show_grid, "Show Grid (G)");
+        ui.checkbox(&mut self.canvas_state.isometric, "Isometric (V)");
+        if self.canvas_state.isometric {
+            ui.checkbox(&mut self.canvas_state.blocks, "Block Mode (B)");
+            if self.canvas_state.blocks {
+                ui.add(egui::Slider::new(&mut self.height_step, 1..=16).text("Height step"));
+            }
+        }
+        ui.checkbox(&mut self.canvas_state.show_footprint, "Tile Footprint");
+        if self.canvas_state.show_footprint {
+            ui.checkbox(&mut self.canvas_state.lock_footprint, "Lock to Footprint");
+        }
+        ui.checkbox(&mut self.canvas_state.tiled, "Tiled Preview (T)");
+        if self.canvas_state.tiled {
+            ui.horizontal(|ui| {
+                for mode in [TileMode::Both, TileMode::Horizontal, TileMode::Vertical] {
+                    ui.radio_value(&mut self.canvas_state.tile_mode, mode, mode.name());
+                }
+            });
+        }
+
+        let mut zoom = self.canvas_state.zoom;
+        if ui
+            .add(egui::Slider::new(&mut zoom, 2.0..=128.0).text("Zoom").logarithmic(true))
+            .changed()
+        {
+            self.canvas_state.zoom = zoom;
+        }
+
+        ui.add_space(12.0);
         ui.separator();
 
         // GenAI section
@@ -344,6 +2539,611 @@ impl SpriteditApp {
         }
     }
 
+    /// Properties and collision for one tile of the grid, exported with
+    /// Tiled tilesets.
+    fn show_tile_section(&mut self, ui: &mut egui::Ui) {
+        let tile_size = self.canvas_state.pixels_per_grid;
+        let (columns, rows) =
+            io::tiled::tile_grid(self.animation.width(), self.animation.height(), tile_size);
+        ui.label("Tile");
+        if columns == 0 || rows == 0 {
+            ui.weak("Sprite is smaller than one tile");
+            return;
+        }
+        let last = columns * rows - 1;
+        self.selected_tile = self.selected_tile.min(last);
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.selected_tile).range(0..=last));
+            ui.weak(format!(
+                "column {}, row {}",
+                self.selected_tile % columns,
+                self.selected_tile / columns
+            ));
+        });
+        ui.checkbox(&mut self.show_tile_overlay, "Show tile and collision");
+
+        let tile = self.tileset.tile_mut(self.selected_tile);
+        let mut changed = false;
+        ui.label("Properties");
+        let mut remove = None;
+        for (i, (key, value)) in tile.properties.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let key = egui::TextEdit::singleline(key).desired_width(70.0).hint_text("name");
+                changed |= ui.add(key).changed();
+                let value =
+                    egui::TextEdit::singleline(value).desired_width(70.0).hint_text("value");
+                changed |= ui.add(value).changed();
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            tile.properties.remove(i);
+            changed = true;
+        }
+        if ui.button("+ Property").clicked() {
+            tile.properties.push((String::new(), String::new()));
+            changed = true;
+        }
+
+        ui.label("Collision");
+        let mut remove = None;
+        for (i, rect) in tile.collision.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                for value in rect.iter_mut() {
+                    changed |= ui.add(egui::DragValue::new(value).range(0..=tile_size)).changed();
+                }
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            tile.collision.remove(i);
+            changed = true;
+        }
+        if ui.button("+ Collision Rect").clicked() {
+            tile.collision.push([0, 0, tile_size, tile_size]);
+            changed = true;
+        }
+        self.tileset.prune();
+        self.dirty |= changed;
+
+        // On a PICO-8 sheet tile numbers are sprite numbers
+        let is_pico8_sheet = tile_size == pico8::TILE_SIZE
+            && self.animation.width() == pico8::SHEET_SIZE
+            && self.animation.height() == pico8::SHEET_SIZE;
+        if is_pico8_sheet {
+            let cart = self.pico8_cart.get_or_insert_with(Default::default);
+            let flags = &mut cart.flags[self.selected_tile as usize];
+            ui.label("PICO-8 Flags");
+            ui.horizontal(|ui| {
+                for bit in 0..8 {
+                    let mut set = *flags & (1 << bit) != 0;
+                    if ui.checkbox(&mut set, "").on_hover_text(format!("Flag {bit}")).changed() {
+                        *flags ^= 1 << bit;
+                        self.dirty = true;
+                    }
+                }
+            });
+        }
+    }
+
+    /// Feed the selected tile and every collision rect to the canvas.
+    fn update_tile_overlay(&mut self) {
+        let tile_size = self.canvas_state.pixels_per_grid;
+        let columns = self.animation.width() / tile_size;
+        if tile_size <= 1 || columns == 0 || !self.show_tile_overlay {
+            self.canvas_state.tile_highlight = None;
+            self.canvas_state.collision_guides.clear();
+            return;
+        }
+        let origin = |index: u32| ((index % columns) * tile_size, (index / columns) * tile_size);
+        let (x, y) = origin(self.selected_tile);
+        self.canvas_state.tile_highlight = Some([x, y, tile_size, tile_size]);
+        self.canvas_state.collision_guides = self
+            .tileset
+            .tiles
+            .iter()
+            .flat_map(|(&index, tile)| {
+                let (tx, ty) = origin(index);
+                tile.collision
+                    .iter()
+                    .map(move |&[x, y, w, h]| [tx + x, ty + y, w, h])
+            })
+            .collect();
+    }
+
+    fn show_export_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_export_dialog;
+        egui::Window::new("Export")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let (w, h) = (self.animation.width(), self.animation.height());
+                let settings = &mut self.animation.export;
+                ui.add(
+                    egui::Slider::new(&mut settings.scale, 1..=export::MAX_SCALE)
+                        .text("Scale")
+                        .suffix("x"),
+                );
+                ui.checkbox(&mut settings.isometric, "Isometric projection");
+                ui.add_enabled_ui(!settings.isometric, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut settings.grid, "Grid overlay");
+                        ui.color_edit_button_srgba_unmultiplied(&mut settings.grid_color);
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut settings.opaque, "Background");
+                    ui.add_enabled_ui(settings.opaque, |ui| {
+                        ui.color_edit_button_srgba_unmultiplied(&mut settings.background);
+                    });
+                    if !settings.opaque {
+                        ui.weak("transparent");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Padding");
+                    ui.add(egui::DragValue::new(&mut settings.padding).range(0..=256).suffix(" px"));
+                });
+                let output = export::output_size(w, h, settings);
+                match output {
+                    Some((out_w, out_h)) => ui.label(format!("Output: {out_w} x {out_h}")),
+                    None => ui.label("Output is too large; lower the scale or padding."),
+                };
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(output.is_some(), egui::Button::new("Export")).clicked() {
+                        self.export_scaled();
+                        self.show_export_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_export_dialog = false;
+                    }
+                });
+            });
+        self.show_export_dialog = self.show_export_dialog && open;
+    }
+
+    fn show_image_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_image_dialog;
+        egui::Window::new("Export Image")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let options = &mut self.image_options;
+                ui.horizontal(|ui| {
+                    for format in ImageFormat::ALL {
+                        ui.radio_value(&mut options.format, format, format.name());
+                    }
+                });
+                if options.format.is_icon() {
+                    ui.add_space(4.0);
+                    ui.label("Sizes");
+                    ui.horizontal_wrapped(|ui| {
+                        for (size, on) in raster::ICON_SIZES.iter().zip(&mut options.icon_sizes) {
+                            ui.checkbox(on, format!("{size}"));
+                        }
+                    });
+                    ui.weak("Each size is scaled with nearest neighbor.");
+                }
+                if options.format == ImageFormat::Cur {
+                    let (w, h) = (self.animation.width(), self.animation.height());
+                    ui.horizontal(|ui| {
+                        ui.label("Hotspot");
+                        ui.add(egui::DragValue::new(&mut options.hotspot.0).range(0..=w - 1));
+                        ui.add(egui::DragValue::new(&mut options.hotspot.1).range(0..=h - 1));
+                    });
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Export").clicked() {
+                        self.export_image();
+                        self.show_image_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_image_dialog = false;
+                    }
+                });
+            });
+        self.show_image_dialog = self.show_image_dialog && open;
+    }
+
+    /// Ask whether to keep an indexed PNG's palette as swatches.
+    fn show_palette_prompt(&mut self, ctx: &egui::Context) {
+        let Some(colors) = &self.palette_prompt else {
+            return;
+        };
+        let mut choice = None;
+        egui::Window::new("Indexed PNG")
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "This PNG uses a {}-color palette. The pixels were converted to RGBA.",
+                    colors.len()
+                ));
+                ui.label("Keep it as swatches? Saving writes an indexed PNG in its original order.");
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Keep Palette").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Discard").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+        match choice {
+            Some(true) => {
+                self.palette = self.palette_prompt.take();
+                self.dirty = true;
+            }
+            Some(false) => self.palette_prompt = None,
+            None => {}
+        }
+    }
+
+    /// Offer to restore the documents the last run left unsaved.
+    fn show_recovery_prompt(&mut self, ctx: &egui::Context) {
+        let Some(&latest) = self.recovery_prompt.first() else {
+            return;
+        };
+        egui::Window::new("Recover Unsaved Work")
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} document(s) have unsaved work autosaved, the latest {}.",
+                    self.recovery_prompt.len(),
+                    recovery::age(latest.time)
+                ));
+                ui.label(
+                    "Restore them, each in its own tab? Discarding deletes all recovery snapshots.",
+                );
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Restore").clicked() {
+                        let snapshots = std::mem::take(&mut self.recovery_prompt);
+                        self.recovery_request = Some(RecoveryRequest::Restore(snapshots));
+                    }
+                    if ui.button("Discard").clicked() {
+                        self.recovery_prompt.clear();
+                        self.recovery_request = Some(RecoveryRequest::DiscardAll);
+                    }
+                });
+            });
+    }
+
+    fn show_autosave_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_autosave_dialog;
+        egui::Window::new("Autosave & Recovery")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let settings = &mut self.session.autosave;
+                egui::Grid::new("autosave_settings").num_columns(2).show(ui, |ui| {
+                    ui.label("Snapshot every:");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.interval_secs)
+                            .range(0..=3600)
+                            .suffix(" s"),
+                    )
+                    .on_hover_text("0 turns autosave off");
+                    ui.end_row();
+                    ui.label("Snapshots kept:");
+                    ui.add(egui::DragValue::new(&mut settings.keep).range(1..=50));
+                    ui.end_row();
+                });
+                ui.separator();
+                if self.snapshots.is_empty() {
+                    ui.weak("No snapshots");
+                }
+                // Numbered by how recently each document was snapshotted
+                let documents = recovery::latest_per_document(&self.snapshots);
+                for &snapshot in &self.snapshots {
+                    let number = documents
+                        .iter()
+                        .position(|latest| latest.document == snapshot.document)
+                        .map_or(0, |i| i + 1);
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Document {number}, {}", recovery::age(snapshot.time)));
+                        if ui.button("Restore").clicked() {
+                            let snapshots = vec![snapshot];
+                            self.recovery_request = Some(RecoveryRequest::Restore(snapshots));
+                        }
+                    });
+                }
+                ui.add_space(8.0);
+                let any = !self.snapshots.is_empty();
+                if ui.add_enabled(any, egui::Button::new("Delete All Snapshots")).clicked() {
+                    self.recovery_request = Some(RecoveryRequest::DiscardAll);
+                }
+            });
+        self.show_autosave_dialog = self.show_autosave_dialog && open;
+    }
+
+    /// Ask whether to save before closing a tab, the other tabs or the
+    /// window throws changes away.
+    fn show_unsaved_prompt(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &self.unsaved_prompt else {
+            return;
+        };
+        let (message, save_label) = match pending {
+            PendingClose::Tab => (format!("{} has unsaved changes.", self.document_name()), "Save"),
+            _ => {
+                let others = matches!(pending, PendingClose::Others);
+                let count = (0..self.tabs.len())
+                    .filter(|&i| !(others && i == self.active_tab) && self.tab_info(i).1)
+                    .count();
+                (format!("{count} document(s) have unsaved changes."), "Save All")
+            }
+        };
+        // Some(true) saves first, Some(false) discards the changes
+        let mut choice = None;
+        let mut cancel = false;
+        egui::Window::new("Unsaved Changes")
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(message);
+                ui.label("Save them first?");
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button(save_label).clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Don't Save").clicked() {
+                        choice = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if cancel {
+            self.unsaved_prompt = None;
+            return;
+        }
+        let (Some(save), Some(pending)) = (choice, self.unsaved_prompt.take()) else {
+            return;
+        };
+        // A cancelled or failed save keeps everything open
+        match pending {
+            PendingClose::Tab => {
+                if !save || self.save_file() {
+                    self.remove_tab(self.active_tab);
+                }
+            }
+            PendingClose::Others => {
+                if !save || self.save_dirty_tabs(false) {
+                    self.remove_other_tabs();
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            PendingClose::Window => {
+                if !save {
+                    // Nor offered for restore next time
+                    let closed = self.tabs.iter().flatten().map(|tab| tab.document_id);
+                    self.stale_snapshots.extend(closed);
+                    self.stale_snapshots.push(self.document_id);
+                }
+                if !save || self.save_dirty_tabs(true) {
+                    self.allow_close = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            }
+        }
+    }
+
+    /// Put the document name in the window title, with an asterisk while
+    /// it has unsaved changes.
+    fn update_title(&mut self, ctx: &egui::Context) {
+        let marker = if self.dirty { "*" } else { "" };
+        let title = format!("{}{marker} - Spritedit", self.document_name());
+        if title == self.title {
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = ctx;
+            io::web::set_title(&title);
+        }
+        self.title = title;
+    }
+
+    fn show_code_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_code_dialog;
+        egui::Window::new("Export as Code")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let options = &mut self.code_options;
+                egui::Grid::new("code_export").num_columns(2).show(ui, |ui| {
+                    ui.label("Output:");
+                    ui.horizontal(|ui| {
+                        for output in CodeOutput::ALL {
+                            ui.radio_value(&mut options.output, output, output.name());
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Pixels:");
+                    egui::ComboBox::from_id_salt("code_pixel_format")
+                        .selected_text(options.format.name())
+                        .show_ui(ui, |ui| {
+                            for format in PixelFormat::ALL {
+                                ui.selectable_value(&mut options.format, format, format.name());
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Align rows to:");
+                    egui::ComboBox::from_id_salt("code_row_align")
+                        .selected_text(format!("{} byte(s)", options.row_align))
+                        .show_ui(ui, |ui| {
+                            for align in [1, 2, 4, 8, 16, 32] {
+                                ui.selectable_value(
+                                    &mut options.row_align,
+                                    align,
+                                    format!("{align} byte(s)"),
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut options.name);
+                    ui.end_row();
+                });
+                if options.output == CodeOutput::RawBinary
+                    && options.format.bytes_per_pixel() == 2
+                {
+                    ui.checkbox(&mut options.big_endian, "Big-endian 16-bit pixels");
+                }
+                ui.add_space(4.0);
+                let stride = io::code::row_stride(self.animation.width(), options);
+                let total = stride * self.animation.height() * self.animation.frames.len() as u32;
+                ui.label(format!("{stride} bytes per row, {total} bytes in total"));
+                if options.format == PixelFormat::Indexed8 {
+                    ui.weak("Index 0 is transparent; the palette is RGBA.");
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Export").clicked() {
+                        self.export_code();
+                        self.show_code_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_code_dialog = false;
+                    }
+                });
+            });
+        self.show_code_dialog = self.show_code_dialog && open;
+    }
+
+    fn show_retro_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_retro_dialog;
+        egui::Window::new("Retro Tiles")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for format in TileFormat::ALL {
+                        ui.radio_value(&mut self.retro_format, format, format.name());
+                    }
+                });
+                ui.add_space(4.0);
+
+                let colors = self.retro_format.colors();
+                ui.label(format!("Palette ({colors} colors, index 0 first)"));
+                for row in self.retro_palette[..colors].chunks_mut(8) {
+                    ui.horizontal(|ui| {
+                        for color in row {
+                            ui.color_edit_button_srgba_unmultiplied(color);
+                        }
+                    });
+                }
+                if ui
+                    .button("From Sprite")
+                    .on_hover_text("Use the first colors found in the current frame")
+                    .clicked()
+                {
+                    let picked = retro::palette_from_sprite(self.animation.current(), colors);
+                    self.retro_palette[..colors].copy_from_slice(&picked);
+                }
+                ui.add_space(4.0);
+
+                let (columns, rows) = (
+                    self.animation.width() / retro::TILE_SIZE,
+                    self.animation.height() / retro::TILE_SIZE,
+                );
+                ui.checkbox(&mut self.retro_dedup, "Remove duplicate tiles (writes a .map)");
+                ui.label(format!("{} 8x8 tile(s) in the current frame", columns * rows));
+                if !self.animation.width().is_multiple_of(retro::TILE_SIZE)
+                    || !self.animation.height().is_multiple_of(retro::TILE_SIZE)
+                {
+                    ui.weak("Partial tiles at the right and bottom edges are skipped.");
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Import...").clicked() {
+                        self.import_tiles();
+                        self.show_retro_dialog = false;
+                    }
+                    if ui.button("Export...").clicked() {
+                        self.export_tiles();
+                        self.show_retro_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_retro_dialog = false;
+                    }
+                });
+            });
+        self.show_retro_dialog = self.show_retro_dialog && open;
+    }
+
+    fn show_tiled_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_tiled_dialog;
+        egui::Window::new("Export Tiled Tileset")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let tile_size = self.canvas_state.pixels_per_grid;
+                let (columns, rows) =
+                    io::tiled::tile_grid(self.animation.width(), self.animation.height(), tile_size);
+                let usable = tile_size > 1 && columns > 0 && rows > 0;
+                if usable {
+                    ui.label(format!(
+                        "{tile_size}x{tile_size} tiles, {columns} x {rows} per frame"
+                    ));
+                    if self.animation.frames.len() > 1 {
+                        ui.weak("Tiles that change between frames are exported as animations.");
+                    }
+                } else {
+                    ui.label("Set \"Pixels per grid box\" to the tile size first.");
+                }
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    ui.label("Spacing:");
+                    ui.add(egui::DragValue::new(&mut self.tiled_options.spacing).range(0..=16));
+                    ui.label("Margin:");
+                    ui.add(egui::DragValue::new(&mut self.tiled_options.margin).range(0..=16));
+                });
+                ui.checkbox(&mut self.tiled_options.write_map, "Also write a .tmx map");
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(usable, egui::Button::new("Export")).clicked() {
+                        self.export_tiled();
+                        self.show_tiled_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_tiled_dialog = false;
+                    }
+                });
+            });
+        self.show_tiled_dialog = self.show_tiled_dialog && open;
+    }
+
     fn show_new_sprite_dialog(&mut self, ctx: &egui::Context) {
         let mut open = self.show_new_dialog;
         egui::Window::new("New Sprite")
@@ -367,8 +3167,8 @@ impl SpriteditApp {
                             self.new_width.parse().unwrap_or(16).clamp(1, 256);
                         let h: u32 =
                             self.new_height.parse().unwrap_or(16).clamp(1, 256);
-                        self.sprite = Sprite::new(w, h);
-                        self.canvas_state.offset = egui::Vec2::ZERO;
+                        self.open_sprite(Sprite::new(w, h));
+                        self.mark_clean();
                         self.status_message =
                             format!("Created new {}x{} sprite", w, h);
                         self.show_new_dialog = false;
@@ -378,30 +3178,262 @@ impl SpriteditApp {
                     }
                 });
             });
-        self.show_new_dialog = open;
+        self.show_new_dialog = self.show_new_dialog && open;
+    }
+
+    fn show_gif_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_gif_dialog;
+        egui::Window::new("Export GIF")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} frame(s), {}x{}",
+                    self.animation.frames.len(),
+                    self.animation.width(),
+                    self.animation.height()
+                ));
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    ui.label("Loop count:");
+                    let loops =
+                        egui::DragValue::new(&mut self.animation.loop_count).range(0..=1000);
+                    self.dirty |= ui.add(loops).changed();
+                    ui.weak("0 = forever");
+                });
+                ui.add_space(4.0);
+                ui.label("GIF only supports fully opaque or fully transparent pixels.");
+                ui.add(
+                    egui::Slider::new(&mut self.gif_options.alpha_threshold, 1..=255)
+                        .text("Transparent below alpha"),
+                );
+                let mut use_matte = self.gif_options.matte.is_some();
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut use_matte, "Blend partial alpha onto");
+                    let mut matte = self.gif_options.matte.unwrap_or([255, 255, 255]);
+                    ui.add_enabled(use_matte, |ui: &mut egui::Ui| {
+                        ui.color_edit_button_srgb(&mut matte)
+                    });
+                    self.gif_options.matte = use_matte.then_some(matte);
+                });
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Export").clicked() {
+                        self.export_gif();
+                        self.show_gif_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_gif_dialog = false;
+                    }
+                });
+            });
+        self.show_gif_dialog = self.show_gif_dialog && open;
+    }
+
+    fn show_sheet_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_sheet_dialog;
+        egui::Window::new("Export Sprite Sheet")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let options = &mut self.sheet_options;
+                ui.horizontal(|ui| {
+                    ui.label("Layout:");
+                    for layout in SheetLayout::ALL {
+                        ui.radio_value(&mut options.layout, layout, layout.name());
+                    }
+                });
+                if options.layout == SheetLayout::Grid {
+                    ui.horizontal(|ui| {
+                        ui.label("Columns:");
+                        ui.add(egui::DragValue::new(&mut options.columns).range(1..=256));
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Padding:");
+                    ui.add(egui::DragValue::new(&mut options.padding).range(0..=64));
+                    ui.label("Extrude:");
+                    ui.add(egui::DragValue::new(&mut options.extrude).range(0..=16));
+                });
+                ui.checkbox(&mut options.power_of_two, "Power-of-two size");
+                ui.horizontal(|ui| {
+                    ui.label("Pivot:");
+                    for value in &mut options.pivot {
+                        ui.add(egui::DragValue::new(value).range(0.0..=1.0).speed(0.05));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("JSON:");
+                    ui.radio_value(&mut options.json_format, JsonFormat::Hash, "Hash");
+                    ui.radio_value(&mut options.json_format, JsonFormat::Array, "Array");
+                });
+                ui.add_space(4.0);
+                let (w, h) = io::sheet::sheet_size(&self.animation, options);
+                ui.label(format!(
+                    "{} frame(s) on a {w}x{h} sheet",
+                    self.animation.frames.len()
+                ));
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Export").clicked() {
+                        self.export_sheet();
+                        self.show_sheet_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_sheet_dialog = false;
+                    }
+                });
+            });
+        self.show_sheet_dialog = self.show_sheet_dialog && open;
+    }
+
+    fn show_slice_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_slice_dialog;
+        egui::Window::new("Slice Sprite Sheet")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::RIGHT_TOP, [-16.0, 48.0])
+            .show(ctx, |ui| {
+                let options = &mut self.slice_options;
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut options.mode, SliceMode::Grid, "Grid");
+                    ui.radio_value(&mut options.mode, SliceMode::Islands, "Auto-detect");
+                });
+                match options.mode {
+                    SliceMode::Grid => {
+                        egui::Grid::new("slice_grid").show(ui, |ui| {
+                            for (label, value) in [
+                                ("Cell size:", &mut options.cell_size),
+                                ("Offset:", &mut options.offset),
+                                ("Spacing:", &mut options.spacing),
+                            ] {
+                                let min = if label == "Cell size:" { 1 } else { 0 };
+                                ui.label(label);
+                                ui.add(egui::DragValue::new(&mut value.0).range(min..=4096));
+                                ui.add(egui::DragValue::new(&mut value.1).range(min..=4096));
+                                ui.end_row();
+                            }
+                        });
+                        ui.checkbox(&mut options.skip_empty, "Skip empty cells");
+                    }
+                    SliceMode::Islands => {
+                        ui.label("Each group of touching opaque pixels becomes a frame.");
+                    }
+                }
+
+                let sheet = self.animation.current();
+                let rects = match &self.slice_preview {
+                    Some((cut_options, cut_sheet, rects))
+                        if cut_options == options && cut_sheet == sheet =>
+                    {
+                        rects.clone()
+                    }
+                    _ => {
+                        let rects = io::sheet::slice_rects(sheet, options);
+                        self.canvas_state.slice_guides =
+                            rects.iter().map(|r| [r.x, r.y, r.w, r.h]).collect();
+                        self.slice_preview = Some((options.clone(), sheet.clone(), rects.clone()));
+                        rects
+                    }
+                };
+
+                ui.add_space(4.0);
+                ui.label(format!("{} frame(s)", rects.len()));
+                if self.canvas_state.isometric {
+                    ui.weak("Cut lines are shown in flat view.");
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Slice").clicked() {
+                        match io::sheet::slice(self.animation.current(), &rects) {
+                            Some(animation) => {
+                                self.status_message = format!(
+                                    "Sliced into {} frame(s) of {}x{}",
+                                    animation.frames.len(),
+                                    animation.width(),
+                                    animation.height()
+                                );
+                                self.apply_slices(animation);
+                            }
+                            None => self.status_message = "Nothing to slice".into(),
+                        }
+                        self.show_slice_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_slice_dialog = false;
+                    }
+                });
+            });
+        self.show_slice_dialog = self.show_slice_dialog && open;
+    }
+
+    /// Replace the sheet with the frames sliced from it, as one undoable
+    /// step.
+    fn apply_slices(&mut self, sliced: Animation) {
+        self.drop_floating();
+        // Tags pointed at the sheet's frames, not the slices
+        self.animation.tags.clear();
+        self.animation.frames = sliced.frames;
+        self.animation.current = 0;
+        self.heights = HeightMap::new(self.animation.width(), self.animation.height());
+        self.playing = false;
+        self.canvas_state.selection = None;
+        self.selection_drag = None;
+        self.dirty = true;
+    }
+
+    fn show_iso_tile_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_iso_tile_dialog;
+        egui::Window::new("New Isometric Tile")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("Tile size (2:1):");
+                for (w, h) in iso::TILE_PRESETS {
+                    ui.radio_value(&mut self.iso_tile_size, (w, h), format!("{w} x {h}"));
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Create").clicked() {
+                        let (w, h) = self.iso_tile_size;
+                        self.open_sprite(Sprite::new(w, h));
+                        self.mark_clean();
+                        self.canvas_state.isometric = false;
+                        self.canvas_state.show_footprint = true;
+                        self.canvas_state.lock_footprint = true;
+                        self.status_message =
+                            format!("Created new {}x{} isometric tile", w, h);
+                        self.show_iso_tile_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_iso_tile_dialog = false;
+                    }
+                });
+            });
+        self.show_iso_tile_dialog = self.show_iso_tile_dialog && open;
     }
 
     fn load_from_url(&mut self, url: &str) {
         #[cfg(not(target_arch = "wasm32"))]
         {
             match io::native::fetch_url(url) {
-                Ok(data) => {
-                    if let Some(sprite) = io::png_to_sprite(&data) {
-                        self.status_message =
-                            format!("Loaded {}x{} sprite from URL", sprite.width, sprite.height);
-                        self.sprite = sprite;
-                        self.canvas_state.offset = egui::Vec2::ZERO;
-                    } else {
-                        self.status_message = "Failed to decode image from URL".into();
-                    }
-                }
+                Ok(data) => self.load_from_source(&data, Source::Url(url.into())),
                 Err(e) => {
-                    self.status_message = format!("URL fetch error: {e}");
+                    self.restore_view = None;
+                    self.notifications.error(e);
                 }
             }
         }
         #[cfg(target_arch = "wasm32")]
         {
+            self.pending_url = Some(url.into());
             io::web::fetch_url(url);
             self.status_message = "Fetching image from URL...".into();
         }
@@ -436,7 +3468,7 @@ impl SpriteditApp {
                     }
                 });
             });
-        self.show_url_dialog = open;
+        self.show_url_dialog = self.show_url_dialog && open;
     }
 
     fn show_ai_dialog(&mut self, ctx: &egui::Context) {
@@ -470,20 +3502,65 @@ impl SpriteditApp {
                 ui.add_space(4.0);
                 ui.weak("Connect an API key to enable AI sprite generation.");
             });
-        self.show_ai_dialog = open;
+        self.show_ai_dialog = self.show_ai_dialog && open;
     }
 }
 
 impl eframe::App for SpriteditApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check for async file loads (WASM)
+    /// Called periodically and on exit; eframe writes it to disk natively
+    /// and to localStorage on the web.
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // Also runs as a browser tab closes or reloads. Natively, once the
+        // exit prompt is settled every change was saved or thrown away.
+        #[cfg(not(target_arch = "wasm32"))]
+        let settled = self.allow_close;
+        #[cfg(target_arch = "wasm32")]
+        let settled = false;
+        self.forget_stale_snapshots(Some(&mut *storage));
+        if self.session.autosave.interval_secs > 0 && !settled {
+            self.write_snapshots(Some(storage));
+        }
+        self.session.last = self.source.clone();
+        self.session.view = Some(ViewState::capture(&self.canvas_state));
+        eframe::set_value(storage, session::STORAGE_KEY, &self.session);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // Check for async file loads and clipboard reads (WASM)
         self.check_pending_file();
+        self.check_pending_paste();
+
+        // Pasted pixels only float while the select tool is active
+        if self.current_tool != Tool::Select {
+            self.drop_floating();
+        }
+
+        self.advance_playback(ctx);
+        self.autosave(ctx, frame);
+
+        // Last frame's changes become an undo step, a stroke or drag only
+        // once the pointer is released
+        if !ctx.input(|i| i.pointer.any_down()) {
+            self.check_in_history();
+        }
+
+        // Closing the window with unsaved changes in any tab asks first
+        let any_dirty = (0..self.tabs.len()).any(|i| self.tab_info(i).1);
+        #[cfg(not(target_arch = "wasm32"))]
+        if ctx.input(|i| i.viewport().close_requested()) && any_dirty && !self.allow_close {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.unsaved_prompt = Some(PendingClose::Window);
+        }
+        #[cfg(target_arch = "wasm32")]
+        io::web::warn_before_unload(any_dirty);
+        self.update_title(ctx);
 
         // Global keyboard shortcuts
         self.handle_shortcuts(ctx);
 
         // Command palette overlay
-        if let Some(cmd) = self.command_palette.show(ctx) {
+        let recent: Vec<String> = self.session.recent.iter().map(Source::label).collect();
+        if let Some(cmd) = self.command_palette.show(ctx, &recent) {
             self.execute_command(cmd);
         }
 
@@ -495,20 +3572,152 @@ impl eframe::App for SpriteditApp {
                         self.show_new_dialog = true;
                         ui.close_menu();
                     }
+                    if ui.button("New Isometric Tile...").clicked() {
+                        self.show_iso_tile_dialog = true;
+                        ui.close_menu();
+                    }
                     if ui.button("Open...  Ctrl+O").clicked() {
                         self.open_file();
                         ui.close_menu();
                     }
+                    ui.add_enabled_ui(!self.session.recent.is_empty(), |ui| {
+                        ui.menu_button("Open Recent", |ui| {
+                            let mut reopen = None;
+                            for source in &self.session.recent {
+                                if ui.button(source.label()).on_hover_text(source.full()).clicked()
+                                {
+                                    reopen = Some(source.clone());
+                                }
+                            }
+                            ui.separator();
+                            if ui.button("Clear Recent").clicked() {
+                                self.session.recent.clear();
+                                ui.close_menu();
+                            }
+                            if let Some(source) = reopen {
+                                self.open_recent(source);
+                                ui.close_menu();
+                            }
+                        });
+                    });
+                    ui.checkbox(&mut self.session.reopen_last, "Reopen Last Session")
+                        .on_hover_text("Open the last document with its view on startup");
+                    if ui.button("Autosave & Recovery...").clicked() {
+                        self.show_autosave_dialog = true;
+                        ui.close_menu();
+                    }
                     if ui.button("Load from URL...").clicked() {
                         self.show_url_dialog = true;
                         ui.close_menu();
                     }
+                    if ui.button("Import Sprite Sheet...").clicked() {
+                        self.import_sheet();
+                        ui.close_menu();
+                    }
                     if ui.button("Save  Ctrl+S").clicked() {
                         self.save_file();
                         ui.close_menu();
                     }
+                    if ui.button("Save As...  Ctrl+Shift+S").clicked() {
+                        self.save_file_as();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("New Tab").clicked() {
+                        self.new_tab();
+                        ui.close_menu();
+                    }
+                    if ui.button("Close Tab  Ctrl+W").clicked() {
+                        self.close_tab(self.active_tab);
+                        ui.close_menu();
+                    }
+                    if ui.button("Close Other Tabs").clicked() {
+                        self.close_other_tabs();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Export...  Ctrl+E").clicked() {
+                        self.show_export_dialog = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export BMP / TGA / QOI / ICO...").clicked() {
+                        self.show_image_dialog = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export GIF...").clicked() {
+                        self.show_gif_dialog = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export APNG...").clicked() {
+                        self.export_apng();
+                        ui.close_menu();
+                    }
+                    if ui.button("Export Animated WebP...").clicked() {
+                        self.export_webp();
+                        ui.close_menu();
+                    }
+                    if ui.button("Export Sprite Sheet...").clicked() {
+                        self.show_sheet_dialog = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export Tiled Tileset...").clicked() {
+                        self.show_tiled_dialog = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export Godot SpriteFrames...").clicked() {
+                        self.export_godot();
+                        ui.close_menu();
+                    }
+                    if ui.button("Retro Tiles (CHR / 2bpp / 4bpp)...").clicked() {
+                        self.show_retro_dialog = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export PICO-8 Cart...").clicked() {
+                        self.export_pico8();
+                        ui.close_menu();
+                    }
+                    if ui.button("Export as Code...").clicked() {
+                        self.show_code_dialog = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export Aseprite...").clicked() {
+                        self.export_aseprite();
+                        ui.close_menu();
+                    }
+                    if ui.button("Export Isometric Tile...").clicked() {
+                        self.export_iso_tile();
+                        ui.close_menu();
+                    }
+                    if ui.button("Export Isometric Block...").clicked() {
+                        self.export_iso_block();
+                        ui.close_menu();
+                    }
                 });
                 ui.menu_button("Edit", |ui| {
+                    let can_undo = self.history.can_undo() || self.canvas_state.floating.is_some();
+                    if ui.add_enabled(can_undo, egui::Button::new("Undo  Ctrl+Z")).clicked() {
+                        self.undo();
+                        ui.close_menu();
+                    }
+                    let can_redo = self.history.can_redo();
+                    if ui.add_enabled(can_redo, egui::Button::new("Redo  Ctrl+Shift+Z")).clicked() {
+                        self.redo();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Copy  Ctrl+C").clicked() {
+                        self.copy_to_clipboard();
+                        ui.close_menu();
+                    }
+                    if ui.button("Paste  Ctrl+V").clicked() {
+                        self.paste_from_clipboard(false);
+                        ui.close_menu();
+                    }
+                    if ui.button("Paste as New Sprite  Ctrl+Shift+V").clicked() {
+                        self.paste_from_clipboard(true);
+                        ui.close_menu();
+                    }
+                    ui.separator();
                     if ui.button("Pencil  P").clicked() {
                         self.current_tool = Tool::Pencil;
                         ui.close_menu();
@@ -525,6 +3734,59 @@ impl eframe::App for SpriteditApp {
                         self.current_tool = Tool::ColorPicker;
                         ui.close_menu();
                     }
+                    if ui.button("Select  M").clicked() {
+                        self.current_tool = Tool::Select;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Raise Block  R").clicked() {
+                        self.current_tool = Tool::Raise;
+                        ui.close_menu();
+                    }
+                    if ui.button("Lower Block  L").clicked() {
+                        self.current_tool = Tool::Lower;
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Frame", |ui| {
+                    if ui.button("Play / Stop  Enter").clicked() {
+                        self.toggle_playback();
+                        ui.close_menu();
+                    }
+                    if ui.button("Previous Frame  ,").clicked() {
+                        self.animation.prev_frame();
+                        ui.close_menu();
+                    }
+                    if ui.button("Next Frame  .").clicked() {
+                        self.animation.next_frame();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("New Frame").clicked() {
+                        self.animation.add_frame();
+                        self.dirty = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Duplicate Frame").clicked() {
+                        self.animation.duplicate_frame();
+                        self.dirty = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Delete Frame").clicked() {
+                        self.animation.remove_frame();
+                        self.dirty = true;
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Image", |ui| {
+                    if ui.button("Convert to Isometric").clicked() {
+                        self.convert_to_isometric();
+                        ui.close_menu();
+                    }
+                    if ui.button("Slice into Frames...").clicked() {
+                        self.show_slice_dialog = true;
+                        ui.close_menu();
+                    }
                 });
                 ui.menu_button("View", |ui| {
                     if ui
@@ -539,6 +3801,28 @@ impl eframe::App for SpriteditApp {
                     {
                         ui.close_menu();
                     }
+                    if ui
+                        .checkbox(&mut self.canvas_state.blocks, "Isometric Blocks  B")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                    }
+                    if ui
+                        .checkbox(&mut self.canvas_state.tiled, "Tiled Preview  T")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Tile Direction", |ui| {
+                        for mode in [TileMode::Both, TileMode::Horizontal, TileMode::Vertical] {
+                            if ui
+                                .radio_value(&mut self.canvas_state.tile_mode, mode, mode.name())
+                                .clicked()
+                            {
+                                ui.close_menu();
+                            }
+                        }
+                    });
                     ui.separator();
                     if ui.button("Zoom In  +").clicked() {
                         self.canvas_state.zoom =
@@ -565,16 +3849,30 @@ impl eframe::App for SpriteditApp {
             });
         });
 
+        // Tab strip
+        egui::TopBottomPanel::top("tab_strip").show(ctx, |ui| {
+            self.show_tabs(ui);
+        });
+
         // Status bar
         egui::TopBottomPanel::bottom("status_bar")
             .exact_height(24.0)
             .show(ctx, |ui| {
                 ui.horizontal_centered(|ui| {
+                    let marker = if self.dirty { "*" } else { "" };
+                    ui.strong(format!("{}{marker}", self.document_name()));
+                    ui.separator();
                     ui.label(&self.status_message);
                     ui.separator();
                     ui.label(format!(
                         "{}x{}",
-                        self.sprite.width, self.sprite.height
+                        self.animation.width(), self.animation.height()
+                    ));
+                    ui.separator();
+                    ui.label(format!(
+                        "Frame {}/{}",
+                        self.animation.current + 1,
+                        self.animation.frames.len()
                     ));
                     ui.separator();
                     ui.label(format!("Tool: {}", self.current_tool.name()));
@@ -583,6 +3881,14 @@ impl eframe::App for SpriteditApp {
                 });
             });
 
+        // Bottom panel — frames timeline
+        let timeline_rect = egui::TopBottomPanel::bottom("timeline")
+            .show(ctx, |ui| {
+                self.show_timeline(ui);
+            })
+            .response
+            .rect;
+
         // Left panel — tools
         egui::SidePanel::left("tools_panel")
             .resizable(false)
@@ -599,16 +3905,68 @@ impl eframe::App for SpriteditApp {
             });
 
         // Center — canvas
-        egui::CentralPanel::default().show(ctx, |ui| {
-            let response =
-                canvas::show_canvas(ui, &self.sprite, &mut self.canvas_state);
-            self.handle_canvas_response(response);
-        });
+        self.update_tile_overlay();
+        let canvas_rect = egui::CentralPanel::default()
+            .show(ctx, |ui| {
+                let response = canvas::show_canvas(
+                    ui,
+                    self.animation.current(),
+                    &self.heights,
+                    &mut self.canvas_state,
+                );
+                self.handle_canvas_response(response);
+            })
+            .response
+            .rect;
+
+        // Files dragged in from the OS
+        self.show_drop_target(ctx, canvas_rect, timeline_rect);
+        self.handle_dropped_files(ctx, timeline_rect);
 
         // Dialogs
         if self.show_new_dialog {
             self.show_new_sprite_dialog(ctx);
         }
+        if self.show_iso_tile_dialog {
+            self.show_iso_tile_dialog(ctx);
+        }
+        if self.show_gif_dialog {
+            self.show_gif_dialog(ctx);
+        }
+        if self.show_sheet_dialog {
+            self.show_sheet_dialog(ctx);
+        }
+        if self.show_slice_dialog {
+            self.show_slice_dialog(ctx);
+        }
+        if self.show_tiled_dialog {
+            self.show_tiled_dialog(ctx);
+        }
+        if self.show_export_dialog {
+            self.show_export_dialog(ctx);
+        }
+        if self.show_image_dialog {
+            self.show_image_dialog(ctx);
+        }
+        if self.show_code_dialog {
+            self.show_code_dialog(ctx);
+        }
+        self.show_palette_prompt(ctx);
+        self.show_unsaved_prompt(ctx);
+        if !self.recovery_prompt.is_empty() {
+            self.show_recovery_prompt(ctx);
+        }
+        if self.show_autosave_dialog {
+            self.show_autosave_dialog(ctx);
+        }
+        self.notifications.show(ctx);
+        if self.show_retro_dialog {
+            self.show_retro_dialog(ctx);
+        }
+        if !self.show_slice_dialog {
+            self.canvas_state.slice_guides.clear();
+            self.slice_preview = None;
+        }
         if self.show_url_dialog {
             self.show_url_dialog(ctx);
         }
@@ -617,3 +3975,34 @@ impl eframe::App for SpriteditApp {
         }
     }
 }
+
+/// Name to show for a dropped file: its path's file name natively, the
+/// browser-supplied name on the web.
+fn dropped_file_name(file: &egui::DroppedFile) -> String {
+    file.path
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map_or_else(|| file.name.clone(), |n| n.to_string_lossy().into_owned())
+}
+
+/// Contents of a dropped file. Browsers hand over the bytes; native drops
+/// only give a path to read.
+fn dropped_file_bytes(file: &egui::DroppedFile) -> Result<Vec<u8>, io::Error> {
+    if let Some(bytes) = &file.bytes {
+        io::Error::check_size(bytes.len() as u64)?;
+        return Ok(bytes.to_vec());
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = &file.path {
+        return io::native::read_file(path);
+    }
+    Err(io::Error::File(format!("{} has no readable data", dropped_file_name(file))))
+}
+
+/// Frame index editor, shown 1-based to match the timeline's frame buttons.
+fn frame_number(value: &mut usize, last: usize) -> egui::DragValue<'_> {
+    egui::DragValue::new(value)
+        .range(0..=last)
+        .custom_formatter(|n, _| format!("{}", n as usize + 1))
+        .custom_parser(|text| text.parse::<f64>().ok().map(|n| n - 1.0))
+}
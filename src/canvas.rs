@@ -1,13 +1,91 @@
 use egui::{Color32, Pos2, Rect, Stroke, Vec2, pos2, vec2};
+use serde::{Deserialize, Serialize};
 
+use crate::iso::{self, HeightMap};
+use crate::selection::Floating;
 use crate::sprite::Sprite;
 
+/// Which axes the tiled preview repeats the sprite along.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileMode {
+    Both,
+    Horizontal,
+    Vertical,
+}
+
+impl TileMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileMode::Both => "Both",
+            TileMode::Horizontal => "Horizontal",
+            TileMode::Vertical => "Vertical",
+        }
+    }
+
+    fn repeats_x(&self) -> bool {
+        matches!(self, TileMode::Both | TileMode::Horizontal)
+    }
+
+    fn repeats_y(&self) -> bool {
+        matches!(self, TileMode::Both | TileMode::Vertical)
+    }
+}
+
 pub struct CanvasState {
     pub zoom: f32,
     pub offset: Vec2,
     pub show_grid: bool,
     pub isometric: bool,
+    /// Extrude pixels by their height map in isometric view
+    pub blocks: bool,
+    pub tiled: bool,
+    pub tile_mode: TileMode,
+    /// Overlay the isometric tile footprint in flat view
+    pub show_footprint: bool,
+    /// Refuse to paint outside the isometric tile footprint
+    pub lock_footprint: bool,
     pub pixels_per_grid: u32,
+    /// Cut lines previewed while slicing a sprite sheet, as pixel
+    /// rectangles `[x, y, width, height]`
+    pub slice_guides: Vec<[u32; 4]>,
+    /// Tile being edited in the tile properties, outlined in flat view
+    pub tile_highlight: Option<[u32; 4]>,
+    /// Collision rectangles to overlay, in sprite pixels
+    pub collision_guides: Vec<[u32; 4]>,
+    /// Rectangle picked with the select tool, outlined in flat view
+    pub selection: Option<[u32; 4]>,
+    /// Pasted pixels drawn over the sprite in flat view until dropped
+    pub floating: Option<Floating>,
+    /// Texture of the tiled preview copies and the pixels it was uploaded
+    /// from, re-uploaded only when the sprite changes
+    tiled_texture: Option<(egui::TextureHandle, Sprite)>,
+}
+
+impl CanvasState {
+    /// Map a canvas pixel coordinate onto the sprite. In tiled mode, positions
+    /// on the repeated copies wrap around to the original.
+    pub fn wrap_pixel(&self, x: i32, y: i32, sprite: &Sprite) -> Option<(u32, u32)> {
+        let w = sprite.width as i32;
+        let h = sprite.height as i32;
+        let tiled = self.tiled && !self.isometric;
+        let (x, y) = (
+            if tiled && self.tile_mode.repeats_x() && (-w..2 * w).contains(&x) {
+                x.rem_euclid(w)
+            } else {
+                x
+            },
+            if tiled && self.tile_mode.repeats_y() && (-h..2 * h).contains(&y) {
+                y.rem_euclid(h)
+            } else {
+                y
+            },
+        );
+        if x >= 0 && y >= 0 && x < w && y < h {
+            Some((x as u32, y as u32))
+        } else {
+            None
+        }
+    }
 }
 
 impl Default for CanvasState {
@@ -17,20 +95,34 @@ impl Default for CanvasState {
             offset: Vec2::ZERO,
             show_grid: true,
             isometric: false,
+            blocks: false,
+            tiled: false,
+            tile_mode: TileMode::Both,
+            show_footprint: false,
+            lock_footprint: false,
             pixels_per_grid: 1,
+            slice_guides: Vec::new(),
+            tile_highlight: None,
+            collision_guides: Vec::new(),
+            selection: None,
+            floating: None,
+            tiled_texture: None,
         }
     }
 }
 
 pub struct CanvasResponse {
     pub hovered_pixel: Option<(u32, u32)>,
-    pub painted_pixels: Vec<(u32, u32)>,
+    /// Canvas coordinates under the brush. These may lie on a tiled copy;
+    /// use `CanvasState::wrap_pixel` to map them onto the sprite.
+    pub painted_pixels: Vec<(i32, i32)>,
     pub picked_color: Option<[u8; 4]>,
 }
 
 pub fn show_canvas(
     ui: &mut egui::Ui,
     sprite: &Sprite,
+    heights: &HeightMap,
     state: &mut CanvasState,
 ) -> CanvasResponse {
     let available = ui.available_size();
@@ -61,10 +153,23 @@ pub fn show_canvas(
     painter.rect_filled(rect, 0.0, Color32::from_rgb(40, 40, 40));
 
     // Draw sprite
-    if state.isometric {
+    if state.isometric && state.blocks {
+        draw_blocks(&painter, sprite, heights, rect, state);
+    } else if state.isometric {
         draw_isometric(&painter, sprite, rect, state);
     } else {
+        if state.tiled {
+            draw_tiled_copies(ui.ctx(), &painter, sprite, rect, state);
+        } else {
+            state.tiled_texture = None;
+        }
         draw_flat(&painter, sprite, rect, state);
+        if state.show_footprint {
+            draw_footprint(&painter, sprite, rect, state);
+        }
+        draw_tile_overlay(&painter, sprite, rect, state);
+        draw_slice_guides(&painter, sprite, rect, state);
+        draw_selection(&painter, sprite, rect, state);
     }
 
     // Build response
@@ -75,37 +180,49 @@ pub fn show_canvas(
     };
 
     if let Some(mouse_pos) = response.hover_pos() {
-        let pixel = if state.isometric {
+        let (cx, cy) = if state.isometric && state.blocks {
+            screen_to_pixel_blocks(mouse_pos, rect, state, sprite, heights)
+        } else if state.isometric {
             screen_to_pixel_iso(mouse_pos, rect, state, sprite)
         } else {
             screen_to_pixel_flat(mouse_pos, rect, state, sprite)
         };
 
-        if let Some((px, py)) = pixel {
+        if let Some((px, py)) = state.wrap_pixel(cx, cy, sprite) {
             canvas_response.hovered_pixel = Some((px, py));
 
-            // Draw hover highlight
-            if !state.isometric {
+            // Draw hover highlight in the shape the brush covers on screen
+            let highlight = Stroke::new(2.0, Color32::WHITE);
+            if state.isometric {
+                let mut diamond =
+                    iso_cell_diamond(iso_origin(rect, state, sprite), state, cx, cy);
+                if state.blocks && sprite.get_pixel(px, py)[3] > 0 {
+                    let lift = vec2(0.0, heights.get(px, py) as f32 * state.zoom / 2.0);
+                    diamond = diamond.map(|p| p - lift);
+                }
+                painter.add(egui::Shape::convex_polygon(
+                    diamond.to_vec(),
+                    Color32::from_white_alpha(40),
+                    Stroke::NONE,
+                ));
+                painter.add(egui::Shape::closed_line(diamond.to_vec(), highlight));
+            } else {
                 let origin = sprite_origin(rect, state, sprite);
                 let highlight_rect = Rect::from_min_size(
                     pos2(
-                        origin.x + px as f32 * state.zoom,
-                        origin.y + py as f32 * state.zoom,
+                        origin.x + cx as f32 * state.zoom,
+                        origin.y + cy as f32 * state.zoom,
                     ),
                     vec2(state.zoom, state.zoom),
                 );
-                painter.rect_stroke(
-                    highlight_rect,
-                    0.0,
-                    Stroke::new(2.0, Color32::WHITE),
-                );
+                painter.rect_stroke(highlight_rect, 0.0, highlight);
             }
 
             // Paint on primary click/drag
             if response.dragged_by(egui::PointerButton::Primary)
                 || response.clicked_by(egui::PointerButton::Primary)
             {
-                canvas_response.painted_pixels.push((px, py));
+                canvas_response.painted_pixels.push((cx, cy));
             }
 
             // Color pick on right click
@@ -127,6 +244,64 @@ fn sprite_origin(rect: Rect, state: &CanvasState, sprite: &Sprite) -> Pos2 {
     )
 }
 
+/// Draw the repeated copies around the sprite for the tiled preview. The
+/// original itself is drawn by `draw_flat` on top.
+fn draw_tiled_copies(
+    ctx: &egui::Context,
+    painter: &egui::Painter,
+    sprite: &Sprite,
+    rect: Rect,
+    state: &mut CanvasState,
+) {
+    let texture_id = match &mut state.tiled_texture {
+        Some((texture, uploaded)) => {
+            if uploaded.width != sprite.width
+                || uploaded.height != sprite.height
+                || uploaded.pixels != sprite.pixels
+            {
+                texture.set(sprite.to_color_image(), egui::TextureOptions::NEAREST);
+                uploaded.clone_from(sprite);
+            }
+            texture.id()
+        }
+        None => {
+            let texture = ctx.load_texture(
+                "tiled_preview",
+                sprite.to_color_image(),
+                egui::TextureOptions::NEAREST,
+            );
+            let id = texture.id();
+            state.tiled_texture = Some((texture, sprite.clone()));
+            id
+        }
+    };
+    let origin = sprite_origin(rect, state, sprite);
+    let size = vec2(
+        sprite.width as f32 * state.zoom,
+        sprite.height as f32 * state.zoom,
+    );
+    let xs = if state.tile_mode.repeats_x() { -1..=1 } else { 0..=0 };
+    let ys = if state.tile_mode.repeats_y() { -1..=1 } else { 0..=0 };
+    let uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+
+    for ty in ys {
+        for tx in xs.clone() {
+            if tx == 0 && ty == 0 {
+                continue;
+            }
+            let copy_rect = Rect::from_min_size(
+                origin + vec2(tx as f32 * size.x, ty as f32 * size.y),
+                size,
+            );
+            if !rect.intersects(copy_rect) {
+                continue;
+            }
+            painter.rect_filled(copy_rect, 0.0, Color32::from_rgb(180, 180, 180));
+            painter.image(texture_id, copy_rect, uv, Color32::WHITE);
+        }
+    }
+}
+
 fn draw_flat(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &CanvasState) {
     let pixel_size = state.zoom;
     let origin = sprite_origin(rect, state, sprite);
@@ -169,6 +344,18 @@ fn draw_flat(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &Canva
         }
     }
 
+    // Outline the original so it stands out from its tiled copies
+    if state.tiled && !(state.show_grid && state.zoom >= 4.0) {
+        let border = Rect::from_min_size(
+            origin,
+            vec2(
+                sprite.width as f32 * pixel_size,
+                sprite.height as f32 * pixel_size,
+            ),
+        );
+        painter.rect_stroke(border, 0.0, Stroke::new(2.0, Color32::from_rgb(80, 80, 80)));
+    }
+
     // Grid lines
     if state.show_grid && state.zoom >= 4.0 {
         let thin_color = Color32::from_rgba_unmultiplied(100, 100, 100, 60);
@@ -221,27 +408,173 @@ fn draw_flat(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &Canva
     }
 }
 
-fn draw_isometric(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &CanvasState) {
+/// Collision rectangles and the outline of the tile being edited in the
+/// tile properties.
+fn draw_tile_overlay(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &CanvasState) {
+    let origin = sprite_origin(rect, state, sprite);
+    let to_screen = |[x, y, w, h]: [u32; 4]| {
+        Rect::from_min_size(
+            origin + vec2(x as f32 * state.zoom, y as f32 * state.zoom),
+            vec2(w as f32 * state.zoom, h as f32 * state.zoom),
+        )
+    };
+    for &collision in &state.collision_guides {
+        let r = to_screen(collision);
+        painter.rect_filled(r, 0.0, Color32::from_rgba_unmultiplied(255, 60, 60, 50));
+        painter.rect_stroke(r, 0.0, Stroke::new(1.0, Color32::from_rgb(255, 80, 80)));
+    }
+    if let Some(tile) = state.tile_highlight {
+        painter.rect_stroke(to_screen(tile), 0.0, Stroke::new(2.0, Color32::YELLOW));
+    }
+}
+
+/// Cut lines previewed while slicing a sprite sheet.
+fn draw_slice_guides(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &CanvasState) {
+    let origin = sprite_origin(rect, state, sprite);
+    let stroke = Stroke::new(1.5, Color32::from_rgb(255, 120, 200));
+    for &[x, y, w, h] in &state.slice_guides {
+        let cut = Rect::from_min_size(
+            origin + vec2(x as f32 * state.zoom, y as f32 * state.zoom),
+            vec2(w as f32 * state.zoom, h as f32 * state.zoom),
+        );
+        if rect.intersects(cut) {
+            painter.rect_stroke(cut, 0.0, stroke);
+        }
+    }
+}
+
+/// The floating pixels and a marching-ants style outline around them, or
+/// around the selection when nothing floats.
+fn draw_selection(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &CanvasState) {
+    let origin = sprite_origin(rect, state, sprite);
+    let outline = if let Some(floating) = &state.floating {
+        for y in 0..floating.sprite.height {
+            for x in 0..floating.sprite.width {
+                let [r, g, b, a] = floating.sprite.get_pixel(x, y);
+                if a == 0 {
+                    continue;
+                }
+                let pixel_rect = Rect::from_min_size(
+                    origin
+                        + vec2(
+                            (floating.x + x as i32) as f32 * state.zoom,
+                            (floating.y + y as i32) as f32 * state.zoom,
+                        ),
+                    vec2(state.zoom, state.zoom),
+                );
+                if rect.intersects(pixel_rect) {
+                    painter.rect_filled(
+                        pixel_rect,
+                        0.0,
+                        Color32::from_rgba_unmultiplied(r, g, b, a),
+                    );
+                }
+            }
+        }
+        Rect::from_min_size(
+            origin + vec2(floating.x as f32 * state.zoom, floating.y as f32 * state.zoom),
+            vec2(
+                floating.sprite.width as f32 * state.zoom,
+                floating.sprite.height as f32 * state.zoom,
+            ),
+        )
+    } else if let Some([x, y, w, h]) = state.selection {
+        Rect::from_min_size(
+            origin + vec2(x as f32 * state.zoom, y as f32 * state.zoom),
+            vec2(w as f32 * state.zoom, h as f32 * state.zoom),
+        )
+    } else {
+        return;
+    };
+
+    // White dashes over a black line read on any background
+    let corners = [
+        outline.left_top(),
+        outline.right_top(),
+        outline.right_bottom(),
+        outline.left_bottom(),
+        outline.left_top(),
+    ];
+    painter.rect_stroke(outline, 0.0, Stroke::new(1.0, Color32::BLACK));
+    painter.extend(egui::Shape::dashed_line(
+        &corners,
+        Stroke::new(1.0, Color32::WHITE),
+        4.0,
+        4.0,
+    ));
+}
+
+/// Guide overlay for isometric tile authoring: the ideal diamond outline,
+/// with pixels outside the footprint dimmed while painting is locked to it.
+fn draw_footprint(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &CanvasState) {
+    let origin = sprite_origin(rect, state, sprite);
+    let w = sprite.width as f32 * state.zoom;
+    let h = sprite.height as f32 * state.zoom;
+
+    if state.lock_footprint {
+        let dim = Color32::from_black_alpha(110);
+        for y in 0..sprite.height {
+            for x in 0..sprite.width {
+                if iso::diamond_contains(sprite.width, sprite.height, x, y) {
+                    continue;
+                }
+                let pixel_rect = Rect::from_min_size(
+                    origin + vec2(x as f32 * state.zoom, y as f32 * state.zoom),
+                    vec2(state.zoom, state.zoom),
+                );
+                if rect.intersects(pixel_rect) {
+                    painter.rect_filled(pixel_rect, 0.0, dim);
+                }
+            }
+        }
+    }
+
+    let diamond = vec![
+        origin + vec2(w / 2.0, 0.0),
+        origin + vec2(w, h / 2.0),
+        origin + vec2(w / 2.0, h),
+        origin + vec2(0.0, h / 2.0),
+    ];
+    painter.add(egui::Shape::closed_line(
+        diamond,
+        Stroke::new(2.0, Color32::from_rgb(80, 180, 255)),
+    ));
+}
+
+/// Screen position of the top corner of pixel (0, 0) in isometric view.
+fn iso_origin(rect: Rect, state: &CanvasState, sprite: &Sprite) -> Pos2 {
+    let tile_h = state.zoom / 2.0;
+    pos2(
+        rect.center().x + state.offset.x,
+        rect.center().y + state.offset.y - (sprite.height as f32 * tile_h / 2.0),
+    )
+}
+
+/// Corners of a pixel's diamond in isometric view: top, right, bottom, left.
+fn iso_cell_diamond(origin: Pos2, state: &CanvasState, x: i32, y: i32) -> [Pos2; 4] {
     let tile_w = state.zoom;
     let tile_h = state.zoom / 2.0;
-    let center_x = rect.center().x + state.offset.x;
-    let center_y =
-        rect.center().y + state.offset.y - (sprite.height as f32 * tile_h / 2.0);
+    let iso_x = origin.x + (x - y) as f32 * tile_w / 2.0;
+    let iso_y = origin.y + (x + y) as f32 * tile_h / 2.0;
+    [
+        pos2(iso_x, iso_y),
+        pos2(iso_x + tile_w / 2.0, iso_y + tile_h / 2.0),
+        pos2(iso_x, iso_y + tile_h),
+        pos2(iso_x - tile_w / 2.0, iso_y + tile_h / 2.0),
+    ]
+}
+
+fn draw_isometric(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &CanvasState) {
+    let origin = iso_origin(rect, state, sprite);
 
     let light = Color32::from_rgb(200, 200, 200);
     let dark = Color32::from_rgb(160, 160, 160);
 
     for y in 0..sprite.height {
         for x in 0..sprite.width {
-            let iso_x = center_x + (x as f32 - y as f32) * tile_w / 2.0;
-            let iso_y = center_y + (x as f32 + y as f32) * tile_h / 2.0;
-
-            let top = pos2(iso_x, iso_y);
-            let right = pos2(iso_x + tile_w / 2.0, iso_y + tile_h / 2.0);
-            let bottom = pos2(iso_x, iso_y + tile_h);
-            let left = pos2(iso_x - tile_w / 2.0, iso_y + tile_h / 2.0);
-
-            let diamond = vec![top, right, bottom, left];
+            let diamond = iso_cell_diamond(origin, state, x as i32, y as i32);
+            let [top, right, bottom, left] = diamond;
+            let diamond = diamond.to_vec();
 
             // Checkerboard
             let check_color = if (x + y) % 2 == 0 { light } else { dark };
@@ -274,49 +607,147 @@ fn draw_isometric(painter: &egui::Painter, sprite: &Sprite, rect: Rect, state: &
     }
 }
 
+/// Convert an RGBA pixel to an egui color.
+fn to_color32([r, g, b, a]: [u8; 4]) -> Color32 {
+    Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// Top face and the two visible side faces of an extruded pixel, in that
+/// order. Each level of height lifts the top face by half a tile.
+fn block_faces(origin: Pos2, state: &CanvasState, x: i32, y: i32, height: u8) -> [[Pos2; 4]; 3] {
+    let [top, right, bottom, left] = iso_cell_diamond(origin, state, x, y);
+    let lift = vec2(0.0, height as f32 * state.zoom / 2.0);
+    [
+        [top - lift, right - lift, bottom - lift, left - lift],
+        [left - lift, bottom - lift, bottom, left],
+        [bottom - lift, right - lift, right, bottom],
+    ]
+}
+
+fn draw_blocks(
+    painter: &egui::Painter,
+    sprite: &Sprite,
+    heights: &HeightMap,
+    rect: Rect,
+    state: &CanvasState,
+) {
+    let origin = iso_origin(rect, state, sprite);
+
+    let light = Color32::from_rgb(200, 200, 200);
+    let dark = Color32::from_rgb(160, 160, 160);
+    let grid_stroke = Stroke::new(1.0, Color32::from_rgba_unmultiplied(100, 100, 100, 60));
+
+    // Back to front: cells on the same anti-diagonal never overlap
+    for depth in 0..sprite.width + sprite.height {
+        for x in depth.saturating_sub(sprite.height - 1)..=depth.min(sprite.width - 1) {
+            let y = depth - x;
+            let color = sprite.get_pixel(x, y);
+            let height = if color[3] > 0 { heights.get(x, y) } else { 0 };
+            let [top, left, right] = block_faces(origin, state, x as i32, y as i32, height);
+
+            if color[3] == 0 {
+                let check_color = if (x + y) % 2 == 0 { light } else { dark };
+                painter.add(egui::Shape::convex_polygon(top.to_vec(), check_color, Stroke::NONE));
+            } else {
+                if height > 0 {
+                    painter.add(egui::Shape::convex_polygon(
+                        left.to_vec(),
+                        to_color32(iso::shade(color, iso::LEFT_FACE_SHADE)),
+                        Stroke::NONE,
+                    ));
+                    painter.add(egui::Shape::convex_polygon(
+                        right.to_vec(),
+                        to_color32(iso::shade(color, iso::RIGHT_FACE_SHADE)),
+                        Stroke::NONE,
+                    ));
+                }
+                painter.add(egui::Shape::convex_polygon(top.to_vec(), to_color32(color), Stroke::NONE));
+            }
+
+            if state.show_grid {
+                painter.add(egui::Shape::closed_line(top.to_vec(), grid_stroke));
+            }
+        }
+    }
+}
+
+/// Canvas pixel under the mouse, relative to the sprite's top-left corner.
+/// The result is unbounded so that tiled copies can be hit as well.
 fn screen_to_pixel_flat(
     mouse: Pos2,
     rect: Rect,
     state: &CanvasState,
     sprite: &Sprite,
-) -> Option<(u32, u32)> {
+) -> (i32, i32) {
     let origin = sprite_origin(rect, state, sprite);
     let rel_x = mouse.x - origin.x;
     let rel_y = mouse.y - origin.y;
-    if rel_x >= 0.0 && rel_y >= 0.0 {
-        let px = (rel_x / state.zoom) as u32;
-        let py = (rel_y / state.zoom) as u32;
-        if px < sprite.width && py < sprite.height {
-            return Some((px, py));
-        }
-    }
-    None
+    (
+        (rel_x / state.zoom).floor() as i32,
+        (rel_y / state.zoom).floor() as i32,
+    )
 }
 
+/// Canvas pixel under the mouse in isometric view. Like the flat variant the
+/// result is unbounded; coordinates are floored so that positions left of or
+/// above the sprite's top corner don't truncate onto row or column zero.
 fn screen_to_pixel_iso(
     mouse: Pos2,
     rect: Rect,
     state: &CanvasState,
     sprite: &Sprite,
-) -> Option<(u32, u32)> {
+) -> (i32, i32) {
     let tile_w = state.zoom;
     let tile_h = state.zoom / 2.0;
-    let center_x = rect.center().x + state.offset.x;
-    let center_y =
-        rect.center().y + state.offset.y - (sprite.height as f32 * tile_h / 2.0);
+    let origin = iso_origin(rect, state, sprite);
 
-    let rel_x = mouse.x - center_x;
-    let rel_y = mouse.y - center_y;
+    let rel_x = mouse.x - origin.x;
+    let rel_y = mouse.y - origin.y;
 
     let col = (rel_x / (tile_w / 2.0) + rel_y / (tile_h / 2.0)) / 2.0;
     let row = (rel_y / (tile_h / 2.0) - rel_x / (tile_w / 2.0)) / 2.0;
 
-    if col >= 0.0 && row >= 0.0 {
-        let px = col as u32;
-        let py = row as u32;
-        if px < sprite.width && py < sprite.height {
-            return Some((px, py));
+    (col.floor() as i32, row.floor() as i32)
+}
+
+fn point_in_convex(point: Pos2, polygon: &[Pos2]) -> bool {
+    let mut sign = 0.0f32;
+    for i in 0..polygon.len() {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % polygon.len()];
+        let cross = (b - a).x * (point - a).y - (b - a).y * (point - a).x;
+        if cross != 0.0 {
+            if sign != 0.0 && cross.signum() != sign {
+                return false;
+            }
+            sign = cross.signum();
+        }
+    }
+    true
+}
+
+/// Canvas pixel under the mouse in block mode. Raised blocks are tested front
+/// to back so their top and side faces hide the cells behind them; empty
+/// space falls back to the floor plane.
+fn screen_to_pixel_blocks(
+    mouse: Pos2,
+    rect: Rect,
+    state: &CanvasState,
+    sprite: &Sprite,
+    heights: &HeightMap,
+) -> (i32, i32) {
+    let origin = iso_origin(rect, state, sprite);
+    for depth in (0..sprite.width + sprite.height).rev() {
+        for x in depth.saturating_sub(sprite.height - 1)..=depth.min(sprite.width - 1) {
+            let y = depth - x;
+            if sprite.get_pixel(x, y)[3] == 0 || heights.get(x, y) == 0 {
+                continue;
+            }
+            let faces = block_faces(origin, state, x as i32, y as i32, heights.get(x, y));
+            if faces.iter().any(|face| point_in_convex(mouse, face)) {
+                return (x as i32, y as i32);
+            }
         }
     }
-    None
+    screen_to_pixel_iso(mouse, rect, state, sprite)
 }
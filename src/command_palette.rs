@@ -1,19 +1,63 @@
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Command {
     NewSprite,
+    NewIsoTile,
     OpenFile,
     LoadFromURL,
+    ImportSheet,
+    SliceSheet,
     SaveFile,
+    SaveAs,
+    NewTab,
+    CloseTab,
+    CloseOtherTabs,
+    ExportScaled,
+    ExportImage,
+    ExportGif,
+    ExportApng,
+    ExportWebp,
+    ExportSheet,
+    ExportTiled,
+    ExportGodot,
+    ExportCode,
+    RetroTiles,
+    ExportPico8,
+    ExportAseprite,
+    ExportIsoTile,
+    ExportIsoBlock,
+    ConvertToIsometric,
     ToggleGrid,
     ToggleIsometric,
+    ToggleBlocks,
+    ToggleFootprintLock,
+    ToggleTiled,
+    AddFrame,
+    DuplicateFrame,
+    DeleteFrame,
+    PrevFrame,
+    NextFrame,
+    TogglePlayback,
     SetPencil,
     SetEraser,
     SetFill,
     SetColorPicker,
+    SetRaise,
+    SetLower,
+    SetSelect,
+    Undo,
+    Redo,
+    Copy,
+    Paste,
+    PasteAsSprite,
     ZoomIn,
     ZoomOut,
     ResetView,
     GenerateAI,
+    /// Index into the recent files list
+    OpenRecent(usize),
+    ClearRecent,
+    ToggleReopenLast,
+    Autosave,
 }
 
 pub struct CommandEntry {
@@ -28,6 +72,7 @@ pub struct CommandPalette {
     pub selected_index: usize,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for CommandPalette {
     fn default() -> Self {
         Self {
@@ -46,21 +91,146 @@ impl CommandPalette {
                 shortcut: "Ctrl+N",
                 command: Command::NewSprite,
             },
+            CommandEntry {
+                name: "New Isometric Tile...",
+                shortcut: "",
+                command: Command::NewIsoTile,
+            },
             CommandEntry {
                 name: "Open File...",
                 shortcut: "Ctrl+O",
                 command: Command::OpenFile,
             },
+            CommandEntry {
+                name: "Clear Recent Files",
+                shortcut: "",
+                command: Command::ClearRecent,
+            },
+            CommandEntry {
+                name: "Toggle Reopen Last Session",
+                shortcut: "",
+                command: Command::ToggleReopenLast,
+            },
+            CommandEntry {
+                name: "Autosave & Recovery...",
+                shortcut: "",
+                command: Command::Autosave,
+            },
             CommandEntry {
                 name: "Load from URL...",
                 shortcut: "",
                 command: Command::LoadFromURL,
             },
+            CommandEntry {
+                name: "Import Sprite Sheet...",
+                shortcut: "",
+                command: Command::ImportSheet,
+            },
+            CommandEntry {
+                name: "Slice into Frames...",
+                shortcut: "",
+                command: Command::SliceSheet,
+            },
             CommandEntry {
                 name: "Save File",
                 shortcut: "Ctrl+S",
                 command: Command::SaveFile,
             },
+            CommandEntry {
+                name: "Save As...",
+                shortcut: "Ctrl+Shift+S",
+                command: Command::SaveAs,
+            },
+            CommandEntry {
+                name: "New Tab",
+                shortcut: "",
+                command: Command::NewTab,
+            },
+            CommandEntry {
+                name: "Close Tab",
+                shortcut: "Ctrl+W",
+                command: Command::CloseTab,
+            },
+            CommandEntry {
+                name: "Close Other Tabs",
+                shortcut: "",
+                command: Command::CloseOtherTabs,
+            },
+            CommandEntry {
+                name: "Export Scaled PNG...",
+                shortcut: "Ctrl+E",
+                command: Command::ExportScaled,
+            },
+            CommandEntry {
+                name: "Export BMP / TGA / QOI / ICO...",
+                shortcut: "",
+                command: Command::ExportImage,
+            },
+            CommandEntry {
+                name: "Export GIF...",
+                shortcut: "",
+                command: Command::ExportGif,
+            },
+            CommandEntry {
+                name: "Export APNG...",
+                shortcut: "",
+                command: Command::ExportApng,
+            },
+            CommandEntry {
+                name: "Export Animated WebP...",
+                shortcut: "",
+                command: Command::ExportWebp,
+            },
+            CommandEntry {
+                name: "Export Sprite Sheet...",
+                shortcut: "",
+                command: Command::ExportSheet,
+            },
+            CommandEntry {
+                name: "Export Tiled Tileset...",
+                shortcut: "",
+                command: Command::ExportTiled,
+            },
+            CommandEntry {
+                name: "Export Godot SpriteFrames...",
+                shortcut: "",
+                command: Command::ExportGodot,
+            },
+            CommandEntry {
+                name: "Export as Code...",
+                shortcut: "",
+                command: Command::ExportCode,
+            },
+            CommandEntry {
+                name: "Retro Tiles (NES / Game Boy / SNES)...",
+                shortcut: "",
+                command: Command::RetroTiles,
+            },
+            CommandEntry {
+                name: "Export PICO-8 Cart...",
+                shortcut: "",
+                command: Command::ExportPico8,
+            },
+            CommandEntry {
+                name: "Export Aseprite...",
+                shortcut: "",
+                command: Command::ExportAseprite,
+            },
+            CommandEntry {
+                name: "Export Isometric Tile...",
+                shortcut: "",
+                command: Command::ExportIsoTile,
+            },
+            CommandEntry {
+                name: "Export Isometric Block...",
+                shortcut: "",
+                command: Command::ExportIsoBlock,
+            },
+            CommandEntry {
+                name: "Convert to Isometric",
+                shortcut: "",
+                command: Command::ConvertToIsometric,
+            },
             CommandEntry {
                 name: "Toggle Grid",
                 shortcut: "G",
@@ -71,6 +241,51 @@ impl CommandPalette {
                 shortcut: "V",
                 command: Command::ToggleIsometric,
             },
+            CommandEntry {
+                name: "Toggle Isometric Block Mode",
+                shortcut: "B",
+                command: Command::ToggleBlocks,
+            },
+            CommandEntry {
+                name: "Toggle Footprint Lock",
+                shortcut: "",
+                command: Command::ToggleFootprintLock,
+            },
+            CommandEntry {
+                name: "Toggle Tiled Preview",
+                shortcut: "T",
+                command: Command::ToggleTiled,
+            },
+            CommandEntry {
+                name: "New Frame",
+                shortcut: "",
+                command: Command::AddFrame,
+            },
+            CommandEntry {
+                name: "Duplicate Frame",
+                shortcut: "",
+                command: Command::DuplicateFrame,
+            },
+            CommandEntry {
+                name: "Delete Frame",
+                shortcut: "",
+                command: Command::DeleteFrame,
+            },
+            CommandEntry {
+                name: "Previous Frame",
+                shortcut: ",",
+                command: Command::PrevFrame,
+            },
+            CommandEntry {
+                name: "Next Frame",
+                shortcut: ".",
+                command: Command::NextFrame,
+            },
+            CommandEntry {
+                name: "Play / Stop Animation",
+                shortcut: "Enter",
+                command: Command::TogglePlayback,
+            },
             CommandEntry {
                 name: "Pencil Tool",
                 shortcut: "P",
@@ -91,6 +306,46 @@ impl CommandPalette {
                 shortcut: "I",
                 command: Command::SetColorPicker,
             },
+            CommandEntry {
+                name: "Raise Block Tool",
+                shortcut: "R",
+                command: Command::SetRaise,
+            },
+            CommandEntry {
+                name: "Lower Block Tool",
+                shortcut: "L",
+                command: Command::SetLower,
+            },
+            CommandEntry {
+                name: "Select Tool",
+                shortcut: "M",
+                command: Command::SetSelect,
+            },
+            CommandEntry {
+                name: "Undo",
+                shortcut: "Ctrl+Z",
+                command: Command::Undo,
+            },
+            CommandEntry {
+                name: "Redo",
+                shortcut: "Ctrl+Shift+Z",
+                command: Command::Redo,
+            },
+            CommandEntry {
+                name: "Copy",
+                shortcut: "Ctrl+C",
+                command: Command::Copy,
+            },
+            CommandEntry {
+                name: "Paste",
+                shortcut: "Ctrl+V",
+                command: Command::Paste,
+            },
+            CommandEntry {
+                name: "Paste as New Sprite",
+                shortcut: "Ctrl+Shift+V",
+                command: Command::PasteAsSprite,
+            },
             CommandEntry {
                 name: "Zoom In",
                 shortcut: "+",
@@ -120,14 +375,24 @@ impl CommandPalette {
         self.selected_index = 0;
     }
 
-    /// Show the command palette overlay. Returns a command if one was executed.
-    pub fn show(&mut self, ctx: &egui::Context) -> Option<Command> {
+    /// Show the command palette overlay, with an "Open Recent" entry for
+    /// each of `recent`. Returns a command if one was executed.
+    pub fn show(&mut self, ctx: &egui::Context, recent: &[String]) -> Option<Command> {
         if !self.is_open {
             return None;
         }
 
         let mut executed = None;
-        let commands = Self::commands();
+        let recent: Vec<(String, Command)> = recent
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (format!("Open Recent: {name}"), Command::OpenRecent(i)))
+            .collect();
+        let commands: Vec<(&str, &str, Command)> = Self::commands()
+            .into_iter()
+            .map(|c| (c.name, c.shortcut, c.command))
+            .chain(recent.iter().map(|(name, command)| (name.as_str(), "", *command)))
+            .collect();
 
         egui::Area::new(egui::Id::new("command_palette"))
             .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
@@ -160,9 +425,9 @@ impl CommandPalette {
 
                         // Filter commands
                         let query_lower = self.query.to_lowercase();
-                        let filtered: Vec<&CommandEntry> = commands
+                        let filtered: Vec<&(&str, &str, Command)> = commands
                             .iter()
-                            .filter(|c| c.name.to_lowercase().contains(&query_lower))
+                            .filter(|(name, _, _)| name.to_lowercase().contains(&query_lower))
                             .collect();
 
                         // Arrow key navigation
@@ -178,8 +443,8 @@ impl CommandPalette {
 
                         // Enter to execute
                         if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                            if let Some(entry) = filtered.get(self.selected_index) {
-                                executed = Some(entry.command);
+                            if let Some(&&(_, _, command)) = filtered.get(self.selected_index) {
+                                executed = Some(command);
                                 self.is_open = false;
                             }
                         }
@@ -190,21 +455,22 @@ impl CommandPalette {
                             .max_height(300.0)
                             .show(ui, |ui| {
                                 for (i, entry) in filtered.iter().enumerate() {
+                                    let &&(name, shortcut, command) = entry;
                                     let selected = i == self.selected_index;
                                     let response = ui.horizontal(|ui| {
-                                        let label = ui.selectable_label(selected, entry.name);
-                                        if !entry.shortcut.is_empty() {
+                                        let label = ui.selectable_label(selected, name);
+                                        if !shortcut.is_empty() {
                                             ui.with_layout(
                                                 egui::Layout::right_to_left(egui::Align::Center),
                                                 |ui| {
-                                                    ui.weak(entry.shortcut);
+                                                    ui.weak(shortcut);
                                                 },
                                             );
                                         }
                                         label
                                     });
                                     if response.inner.clicked() {
-                                        executed = Some(entry.command);
+                                        executed = Some(command);
                                         self.is_open = false;
                                     }
                                 }
@@ -0,0 +1,106 @@
+//! Scaled PNG export for sharing sprites: integer upscaling, a grid
+//! overlay, a background and a padding border.
+
+use serde::{Deserialize, Serialize};
+
+use crate::iso;
+use crate::sprite::{blend_over, Sprite};
+
+pub const MAX_SCALE: u32 = 32;
+
+/// Settings for the Export dialog. They're stored with the document so
+/// each sprite keeps its own.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExportSettings {
+    /// Output pixels per sprite pixel
+    pub scale: u32,
+    /// Draw the canvas grid into the image
+    pub grid: bool,
+    pub grid_color: [u8; 4],
+    /// Fill transparent pixels and the border with `background`
+    pub opaque: bool,
+    pub background: [u8; 4],
+    /// Border around the sprite, in output pixels
+    pub padding: u32,
+    /// Export the isometric projection instead of the flat sprite
+    pub isometric: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            scale: 4,
+            grid: false,
+            grid_color: [0, 0, 0, 96],
+            opaque: false,
+            background: [255, 255, 255, 255],
+            padding: 0,
+            isometric: false,
+        }
+    }
+}
+
+/// Output size for a `width` x `height` sprite, or `None` when the image
+/// would be too large to hold.
+pub fn output_size(width: u32, height: u32, settings: &ExportSettings) -> Option<(u32, u32)> {
+    let (w, h) = if settings.isometric {
+        iso::projected_size(width, height)
+    } else {
+        (width, height)
+    };
+    let scale = settings.scale.max(1);
+    let border = settings.padding.checked_mul(2)?;
+    let w = w.checked_mul(scale)?.checked_add(border)?;
+    let h = h.checked_mul(scale)?.checked_add(border)?;
+    // RGBA bytes, as allocated by `Sprite::new`
+    w.checked_mul(h)?.checked_mul(4)?;
+    Some((w, h))
+}
+
+/// Render the sprite with `settings`. Grid lines fall every `grid_size`
+/// sprite pixels, like the canvas grid; the isometric projection has none.
+/// `None` when the output is too large, see `output_size`.
+pub fn render(sprite: &Sprite, settings: &ExportSettings, grid_size: u32) -> Option<Sprite> {
+    let (w, h) = output_size(sprite.width, sprite.height, settings)?;
+    let source = if settings.isometric {
+        iso::project_flat(sprite)
+    } else {
+        sprite.clone()
+    };
+    let scale = settings.scale.max(1);
+    let pad = settings.padding;
+    let mut out = Sprite::new(w, h);
+    if settings.opaque {
+        for px in out.pixels.chunks_exact_mut(4) {
+            px.copy_from_slice(&settings.background);
+        }
+    }
+
+    for y in 0..source.height * scale {
+        for x in 0..source.width * scale {
+            let px = source.get_pixel(x / scale, y / scale);
+            let under = out.get_pixel(pad + x, pad + y);
+            out.set_pixel(pad + x, pad + y, blend_over(px, under));
+        }
+    }
+
+    if settings.grid && !settings.isometric {
+        // One output pixel wide, on the left/top edge of each cell plus the
+        // far right and bottom edges
+        let step = grid_size.max(1) * scale;
+        let (sw, sh) = (source.width * scale, source.height * scale);
+        for y in 0..sh {
+            for x in 0..sw {
+                let on_line = x.is_multiple_of(step)
+                    || y.is_multiple_of(step)
+                    || x == sw - 1
+                    || y == sh - 1;
+                if on_line {
+                    let under = out.get_pixel(pad + x, pad + y);
+                    out.set_pixel(pad + x, pad + y, blend_over(settings.grid_color, under));
+                }
+            }
+        }
+    }
+    Some(out)
+}
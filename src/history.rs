@@ -0,0 +1,119 @@
+//! Undo and redo for a document. Steps are whole copies of what the
+//! document holds rather than recorded edits: between interactions the app
+//! checks the document in, and whatever changed since the last check-in
+//! becomes one step, so a brush stroke or a dialog's changes undo at once.
+
+use crate::animation::Animation;
+use crate::io::pico8;
+use crate::iso::HeightMap;
+use crate::tileset::Tileset;
+
+/// Undo steps kept per document, oldest dropped first.
+const MAX_STEPS: usize = 100;
+/// Pixel and height bytes the steps may hold, so large documents keep
+/// fewer of them.
+const MAX_BYTES: usize = 256 * 1024 * 1024;
+
+/// What undo brings back: the frames with their tags, block heights, tile
+/// data and PICO-8 flags. The current frame and export settings are view
+/// state and stay as they are.
+#[derive(Clone)]
+pub struct State {
+    pub animation: Animation,
+    pub heights: HeightMap,
+    pub tileset: Tileset,
+    pub pico8_cart: Option<pico8::Cart>,
+}
+
+impl State {
+    fn matches(
+        &self,
+        animation: &Animation,
+        heights: &HeightMap,
+        tileset: &Tileset,
+        pico8_cart: &Option<pico8::Cart>,
+    ) -> bool {
+        self.animation.frames == animation.frames
+            && self.animation.loop_count == animation.loop_count
+            && self.animation.tags == animation.tags
+            && self.heights == *heights
+            && self.tileset == *tileset
+            && self.pico8_cart == *pico8_cart
+    }
+
+    fn bytes(&self) -> usize {
+        let pixels: usize = self.animation.frames.iter().map(|f| f.sprite.pixels.len()).sum();
+        pixels + self.heights.heights.len()
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    /// The document as of the last check-in
+    checked_in: Option<State>,
+    undo: Vec<State>,
+    redo: Vec<State>,
+}
+
+impl History {
+    /// Record the document as a step if it changed since the last
+    /// check-in. The first check-in of a new history only takes note of it.
+    pub fn check_in(
+        &mut self,
+        animation: &Animation,
+        heights: &HeightMap,
+        tileset: &Tileset,
+        pico8_cart: &Option<pico8::Cart>,
+    ) {
+        if let Some(last) = &self.checked_in {
+            if last.matches(animation, heights, tileset, pico8_cart) {
+                return;
+            }
+        }
+        let state = State {
+            animation: animation.clone(),
+            heights: heights.clone(),
+            tileset: tileset.clone(),
+            pico8_cart: pico8_cart.clone(),
+        };
+        if let Some(last) = self.checked_in.replace(state) {
+            self.undo.push(last);
+            self.redo.clear();
+            self.trim();
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Step back, returning the state to put back in the document. Check
+    /// the document in first so the latest changes can be redone.
+    pub fn undo(&mut self) -> Option<State> {
+        let state = self.undo.pop()?;
+        if let Some(current) = self.checked_in.replace(state.clone()) {
+            self.redo.push(current);
+        }
+        Some(state)
+    }
+
+    pub fn redo(&mut self) -> Option<State> {
+        let state = self.redo.pop()?;
+        if let Some(current) = self.checked_in.replace(state.clone()) {
+            self.undo.push(current);
+        }
+        Some(state)
+    }
+
+    /// Drop the oldest undo steps past the limits, keeping at least one.
+    fn trim(&mut self) {
+        let mut bytes: usize = self.undo.iter().chain(&self.redo).map(State::bytes).sum();
+        while self.undo.len() > 1 && (self.undo.len() > MAX_STEPS || bytes > MAX_BYTES) {
+            bytes -= self.undo.remove(0).bytes();
+        }
+    }
+}
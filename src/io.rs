@@ -1,9 +1,33 @@
+use crate::animation::Animation;
 use crate::sprite::Sprite;
 
-pub fn sprite_to_png(sprite: &Sprite) -> Vec<u8> {
+pub mod aseprite;
+pub mod clipboard;
+pub mod code;
+mod error;
+pub mod gif;
+pub mod godot;
+pub mod pico8;
+pub mod png_file;
+pub mod raster;
+pub mod retro;
+pub mod sheet;
+pub mod tiled;
+pub mod webp;
+
+pub use error::{Error, MAX_FILE_SIZE};
+
+pub fn sprite_to_png(sprite: &Sprite) -> Result<Vec<u8>, Error> {
     let img =
         image::RgbaImage::from_raw(sprite.width, sprite.height, sprite.pixels.clone())
-            .expect("Invalid sprite dimensions");
+            .ok_or_else(|| {
+                Error::Encode(format!(
+                    "{}x{} sprite has {} bytes of pixels",
+                    sprite.width,
+                    sprite.height,
+                    sprite.pixels.len()
+                ))
+            })?;
     let mut buf = Vec::new();
     let encoder = image::codecs::png::PngEncoder::new(&mut buf);
     image::ImageEncoder::write_image(
@@ -13,182 +37,380 @@ pub fn sprite_to_png(sprite: &Sprite) -> Vec<u8> {
         sprite.height,
         image::ExtendedColorType::Rgba8,
     )
-    .expect("Failed to encode PNG");
-    buf
+    .map_err(|e| Error::Encode(e.to_string()))?;
+    Ok(buf)
+}
+
+/// Encode every frame as an animated PNG, keeping full alpha and each
+/// frame's duration. Viewers without APNG support show the first frame.
+pub fn animation_to_apng(animation: &Animation) -> Result<Vec<u8>, Error> {
+    let err = |e: png::EncodingError| Error::Encode(e.to_string());
+    let mut buf = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut buf, animation.width(), animation.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        // acTL counts plays with 0 meaning forever, same as our loop count
+        encoder
+            .set_animated(animation.frames.len() as u32, animation.loop_count as u32)
+            .map_err(err)?;
+        let mut writer = encoder.write_header().map_err(err)?;
+        for frame in &animation.frames {
+            let delay = frame.duration_ms.min(u16::MAX as u32) as u16;
+            writer.set_frame_delay(delay, 1000).map_err(err)?;
+            writer.write_image_data(&frame.sprite.pixels).map_err(err)?;
+        }
+        writer.finish().map_err(err)?;
+    }
+    Ok(buf)
 }
 
-pub fn png_to_sprite(data: &[u8]) -> Option<Sprite> {
-    let img = image::load_from_memory(data).ok()?.to_rgba8();
-    Some(Sprite {
+/// Decode any supported still image (PNG, JPEG, GIF, BMP, TGA, QOI,
+/// ICO/CUR) to RGBA.
+pub fn decode_image(data: &[u8]) -> Result<Sprite, Error> {
+    Error::check_size(data.len() as u64)?;
+    let img = match image::guess_format(data) {
+        Ok(format) => image::load_from_memory_with_format(data, format).map_err(|e| match e {
+            image::ImageError::Unsupported(_) => Error::UnsupportedFormat,
+            e => Error::corrupt(format.extensions_str()[0], e),
+        })?,
+        // Cursors use the icon decoder
+        Err(_) if raster::is_cursor(data) => {
+            image::load_from_memory_with_format(data, image::ImageFormat::Ico)
+                .map_err(|e| Error::corrupt("cur", e))?
+        }
+        // TGA has no magic number to detect, so it's the last resort and
+        // failing it means we don't know the format
+        Err(_) => image::load_from_memory_with_format(data, image::ImageFormat::Tga)
+            .map_err(|_| Error::UnsupportedFormat)?,
+    }
+    .to_rgba8();
+    Ok(Sprite {
         width: img.width(),
         height: img.height(),
         pixels: img.into_raw(),
     })
 }
 
+/// Decode the frames of any file we open, without the format-specific
+/// extras (cart code, palettes, text chunks).
+pub fn decode_animation(data: &[u8]) -> Result<Animation, Error> {
+    if pico8::is_p8(data) {
+        pico8::read(data).map(|(_, sprite)| Animation::from_sprite(sprite))
+    } else if aseprite::is_aseprite(data) {
+        aseprite::read(data).map(|import| import.animation)
+    } else if gif::is_gif(data) {
+        gif::read(data)
+    } else {
+        decode_image(data).map(Animation::from_sprite)
+    }
+}
+
+/// What decoding a PNG to 8-bit RGBA loses.
+pub struct PngInfo {
+    pub sixteen_bit: bool,
+    /// RGBA palette of an indexed PNG, in file order
+    pub palette: Option<Vec<[u8; 4]>>,
+}
+
+/// Header details of a PNG, or `None` for other formats.
+pub fn png_info(data: &[u8]) -> Option<PngInfo> {
+    let reader = png::Decoder::new(std::io::Cursor::new(data))
+        .read_info()
+        .ok()?;
+    let info = reader.info();
+    let palette = match (&info.color_type, &info.palette) {
+        (png::ColorType::Indexed, Some(rgb)) => {
+            let alpha = info.trns.as_deref().unwrap_or(&[]);
+            let colors = rgb
+                .chunks_exact(3)
+                .enumerate()
+                .map(|(i, c)| [c[0], c[1], c[2], alpha.get(i).copied().unwrap_or(255)])
+                .collect();
+            Some(colors)
+        }
+        _ => None,
+    };
+    Some(PngInfo {
+        sixteen_bit: info.bit_depth == png::BitDepth::Sixteen,
+        palette,
+    })
+}
+
 // --- Native file dialogs ---
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod native {
     use std::io::Read;
+    use std::path::{Path, PathBuf};
+
+    use super::{Error, MAX_FILE_SIZE};
 
-    pub fn open_file_dialog() -> Option<Vec<u8>> {
-        let path = rfd::FileDialog::new()
-            .add_filter("Images", &["png", "jpg", "jpeg", "gif", "bmp"])
-            .pick_file()?;
-        std::fs::read(path).ok()
+    /// Pick and read an image or project file, returning its path too.
+    /// `Ok(None)` if cancelled.
+    pub fn open_file_dialog() -> Result<Option<(PathBuf, Vec<u8>)>, Error> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(
+                "Images",
+                &[
+                    "png", "jpg", "jpeg", "gif", "bmp", "tga", "qoi", "ico", "cur", "ase",
+                    "aseprite", "p8",
+                ],
+            )
+            .pick_file()
+        else {
+            return Ok(None);
+        };
+        read_file(&path).map(|data| Some((path, data)))
+    }
+
+    /// Pick and read a file, offering only the extensions in `filter`.
+    pub fn open_file_dialog_filtered(filter: (&str, &[&str])) -> Result<Option<Vec<u8>>, Error> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(filter.0, filter.1)
+            .pick_file()
+        else {
+            return Ok(None);
+        };
+        read_file(&path).map(Some)
+    }
+
+    pub fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+        let size = std::fs::metadata(path)
+            .map_err(|e| Error::from_io(e, path))?
+            .len();
+        Error::check_size(size)?;
+        std::fs::read(path).map_err(|e| Error::from_io(e, path))
     }
 
-    pub fn fetch_url(url: &str) -> Result<Vec<u8>, String> {
-        let response = ureq::get(url)
-            .call()
-            .map_err(|e| format!("Fetch failed: {e}"))?;
+    pub fn write_file(path: &Path, data: &[u8]) -> Result<(), Error> {
+        std::fs::write(path, data).map_err(|e| Error::from_io(e, path))
+    }
+
+    pub fn fetch_url(url: &str) -> Result<Vec<u8>, Error> {
+        let response = ureq::get(url).call().map_err(|e| match e {
+            ureq::Error::Status(code, _) => Error::HttpStatus(code),
+            ureq::Error::Transport(transport) => Error::Network(transport.to_string()),
+        })?;
         let mut buf = Vec::new();
+        // Read one byte past the limit to tell a full-size file from a
+        // bigger one
         response
             .into_reader()
+            .take(MAX_FILE_SIZE + 1)
             .read_to_end(&mut buf)
-            .map_err(|e| format!("Read failed: {e}"))?;
+            .map_err(|e| Error::Network(e.to_string()))?;
+        Error::check_size(buf.len() as u64)?;
         Ok(buf)
     }
 
-    pub fn save_file_dialog(data: &[u8]) -> bool {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("PNG Image", &["png"])
-            .set_file_name("sprite.png")
-            .save_file()
-        {
-            std::fs::write(path, data).is_ok()
-        } else {
-            false
+    /// Ask where to save `data`. `filter` names the file type and lists its
+    /// extensions, e.g. `("PNG Image", &["png"])`. `Ok(false)` if cancelled.
+    pub fn save_file_dialog(
+        data: &[u8],
+        file_name: &str,
+        filter: (&str, &[&str]),
+    ) -> Result<bool, Error> {
+        match pick_save_path(file_name, filter) {
+            Some(path) => write_file(&path, data).map(|()| true),
+            None => Ok(false),
         }
     }
+
+    /// Ask where to save without writing anything, for exports that put
+    /// several files side by side.
+    pub fn pick_save_path(file_name: &str, filter: (&str, &[&str])) -> Option<PathBuf> {
+        rfd::FileDialog::new()
+            .add_filter(filter.0, filter.1)
+            .set_file_name(file_name)
+            .save_file()
+    }
 }
 
 // --- WASM file I/O ---
 
 #[cfg(target_arch = "wasm32")]
 pub mod web {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
     use wasm_bindgen::prelude::*;
     use wasm_bindgen::JsCast;
 
+    use super::Error;
+
     thread_local! {
-        pub static PENDING_FILE: RefCell<Option<Vec<u8>>> = RefCell::new(None);
+        pub static PENDING_FILE: RefCell<Option<Result<Vec<u8>, Error>>> = RefCell::new(None);
+        static WARN_ON_UNLOAD: Cell<bool> = const { Cell::new(false) };
+        static UNLOAD_LISTENER: Cell<bool> = const { Cell::new(false) };
     }
 
-    pub fn open_file_dialog() {
-        let window = web_sys::window().unwrap();
-        let document = window.document().unwrap();
-        let input: web_sys::HtmlInputElement = document
-            .create_element("input")
-            .unwrap()
+    impl From<JsValue> for Error {
+        fn from(value: JsValue) -> Self {
+            Error::Browser(js_message(&value))
+        }
+    }
+
+    pub(super) fn js_message(value: &JsValue) -> String {
+        value.as_string().unwrap_or_else(|| format!("{value:?}"))
+    }
+
+    fn document() -> Result<web_sys::Document, Error> {
+        web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| Error::Browser("No document".into()))
+    }
+
+    fn create<T: JsCast>(tag: &str) -> Result<T, Error> {
+        document()?
+            .create_element(tag)?
             .dyn_into()
-            .unwrap();
+            .map_err(|_| Error::Browser(format!("<{tag}> has the wrong type")))
+    }
+
+    fn set_pending(result: Result<Vec<u8>, Error>) {
+        PENDING_FILE.with(|f| *f.borrow_mut() = Some(result));
+    }
+
+    pub fn open_file_dialog() -> Result<(), Error> {
+        open_file_dialog_accepting("image/*,.tga,.qoi,.ico,.cur,.ase,.aseprite,.p8")
+    }
+
+    /// Open the browser's file picker for the types in `accept`, e.g.
+    /// `".chr"`. The file arrives later through `check_pending_file`.
+    pub fn open_file_dialog_accepting(accept: &str) -> Result<(), Error> {
+        let input: web_sys::HtmlInputElement = create("input")?;
         input.set_type("file");
-        input.set_accept("image/*");
+        input.set_accept(accept);
 
         let closure = Closure::wrap(Box::new(move |e: web_sys::Event| {
-            let input: web_sys::HtmlInputElement =
-                e.target().unwrap().dyn_into().unwrap();
-            if let Some(files) = input.files() {
-                if let Some(file) = files.get(0) {
-                    let reader = web_sys::FileReader::new().unwrap();
-                    let reader_clone = reader.clone();
-                    let onload =
-                        Closure::wrap(Box::new(move |_: web_sys::Event| {
-                            if let Ok(result) = reader_clone.result() {
-                                let array = js_sys::Uint8Array::new(&result);
-                                let bytes = array.to_vec();
-                                PENDING_FILE
-                                    .with(|f| *f.borrow_mut() = Some(bytes));
-                            }
-                        })
-                            as Box<dyn FnMut(_)>);
-                    reader
-                        .set_onload(Some(onload.as_ref().unchecked_ref()));
-                    onload.forget();
-                    reader.read_as_array_buffer(&file).unwrap();
+            let Some(input) = e
+                .target()
+                .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+            else {
+                return;
+            };
+            if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                if let Err(e) = read_file(&file) {
+                    set_pending(Err(e));
                 }
             }
         }) as Box<dyn FnMut(_)>);
 
-        input
-            .add_event_listener_with_callback(
-                "change",
-                closure.as_ref().unchecked_ref(),
-            )
-            .unwrap();
+        input.add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())?;
         closure.forget();
         input.click();
+        Ok(())
     }
 
-    pub fn save_file(data: &[u8], filename: &str) {
+    /// Start reading `file`; the bytes or the error land in the pending slot.
+    pub fn read_file(file: &web_sys::File) -> Result<(), Error> {
+        Error::check_size(file.size() as u64)?;
+        let reader = web_sys::FileReader::new()?;
+        let reader_clone = reader.clone();
+        let onload = Closure::wrap(Box::new(move |_: web_sys::Event| {
+            let result = reader_clone
+                .result()
+                .map(|buffer| js_sys::Uint8Array::new(&buffer).to_vec())
+                .map_err(Error::from);
+            set_pending(result);
+        }) as Box<dyn FnMut(_)>);
+        reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+        onload.forget();
+        reader.read_as_array_buffer(file)?;
+        Ok(())
+    }
+
+    /// Wrap `data` in a blob of the given MIME type.
+    pub(super) fn blob(data: &[u8], mime_type: &str) -> Result<web_sys::Blob, Error> {
         let array = js_sys::Uint8Array::from(data);
         let blob_parts = js_sys::Array::new();
         blob_parts.push(&array.buffer());
 
         let options = web_sys::BlobPropertyBag::new();
-        options.set_type("image/png");
+        options.set_type(mime_type);
 
-        let blob = web_sys::Blob::new_with_buffer_source_sequence_and_options(
+        Ok(web_sys::Blob::new_with_buffer_source_sequence_and_options(
             &blob_parts,
             &options,
-        )
-        .unwrap();
-        let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
-
-        let window = web_sys::window().unwrap();
-        let document = window.document().unwrap();
-        let anchor: web_sys::HtmlAnchorElement = document
-            .create_element("a")
-            .unwrap()
-            .dyn_into()
-            .unwrap();
+        )?)
+    }
+
+    /// Download `data` as `filename`, tagged with the given MIME type.
+    pub fn save_file(data: &[u8], filename: &str, mime_type: &str) -> Result<(), Error> {
+        let blob = blob(data, mime_type)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+        let anchor: web_sys::HtmlAnchorElement = create("a")?;
         anchor.set_href(&url);
         anchor.set_download(filename);
         anchor.click();
 
         let _ = web_sys::Url::revoke_object_url(&url);
+        Ok(())
     }
 
+    /// Fetch `url` in the background; the bytes or the error land in the
+    /// pending slot.
     pub fn fetch_url(url: &str) {
         let url = url.to_string();
         wasm_bindgen_futures::spawn_local(async move {
-            let opts = web_sys::RequestInit::new();
-            opts.set_method("GET");
-            opts.set_mode(web_sys::RequestMode::Cors);
-
-            let request = web_sys::Request::new_with_str_and_init(&url, &opts)
-                .expect("Failed to create request");
-
-            let window = web_sys::window().unwrap();
-            let resp_value =
-                wasm_bindgen_futures::JsFuture::from(window.fetch_with_request(&request))
-                    .await;
-            let resp_value = match resp_value {
-                Ok(v) => v,
-                Err(_) => return,
-            };
-            let resp: web_sys::Response = resp_value.dyn_into().unwrap();
-            if !resp.ok() {
-                return;
-            }
-            let array_buffer = match resp.array_buffer() {
-                Ok(promise) => {
-                    match wasm_bindgen_futures::JsFuture::from(promise).await {
-                        Ok(buf) => buf,
-                        Err(_) => return,
-                    }
-                }
-                Err(_) => return,
-            };
-            let uint8_array = js_sys::Uint8Array::new(&array_buffer);
-            let bytes = uint8_array.to_vec();
-            PENDING_FILE.with(|f| *f.borrow_mut() = Some(bytes));
+            set_pending(fetch(&url).await);
         });
     }
 
-    pub fn check_pending_file() -> Option<Vec<u8>> {
+    async fn fetch(url: &str) -> Result<Vec<u8>, Error> {
+        let opts = web_sys::RequestInit::new();
+        opts.set_method("GET");
+        opts.set_mode(web_sys::RequestMode::Cors);
+        let request = web_sys::Request::new_with_str_and_init(url, &opts)?;
+
+        let window = web_sys::window().ok_or_else(|| Error::Browser("No window".into()))?;
+        // fetch() only rejects when no response arrives at all
+        let response = wasm_bindgen_futures::JsFuture::from(window.fetch_with_request(&request))
+            .await
+            .map_err(|e| Error::Network(js_message(&e)))?;
+        let response: web_sys::Response = response
+            .dyn_into()
+            .map_err(|_| Error::Network("Not a response".into()))?;
+        if !response.ok() {
+            return Err(Error::HttpStatus(response.status()));
+        }
+        let buffer = wasm_bindgen_futures::JsFuture::from(response.array_buffer()?)
+            .await
+            .map_err(|e| Error::Network(js_message(&e)))?;
+        let bytes = js_sys::Uint8Array::new(&buffer);
+        Error::check_size(bytes.length() as u64)?;
+        Ok(bytes.to_vec())
+    }
+
+    pub fn check_pending_file() -> Option<Result<Vec<u8>, Error>> {
         PENDING_FILE.with(|f| f.borrow_mut().take())
     }
+
+    pub fn set_title(title: &str) {
+        if let Ok(document) = document() {
+            document.set_title(title);
+        }
+    }
+
+    /// Have the browser confirm leaving or reloading the page while `warn`
+    /// is set. The listener is added on the first call.
+    pub fn warn_before_unload(warn: bool) {
+        WARN_ON_UNLOAD.with(|w| w.set(warn));
+        if UNLOAD_LISTENER.with(|added| added.replace(true)) {
+            return;
+        }
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        let closure = Closure::wrap(Box::new(|e: web_sys::BeforeUnloadEvent| {
+            if WARN_ON_UNLOAD.with(Cell::get) {
+                e.prevent_default();
+                // Older browsers only prompt when a return value is set
+                e.set_return_value("");
+            }
+        }) as Box<dyn FnMut(_)>);
+        let _ = window
+            .add_event_listener_with_callback("beforeunload", closure.as_ref().unchecked_ref());
+        closure.forget();
+    }
 }
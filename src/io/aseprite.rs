@@ -0,0 +1,655 @@
+//! Reader and writer for Aseprite's `.ase` / `.aseprite` binary format.
+//!
+//! Spritedit frames are single flat RGBA images, so on import every frame is
+//! composited from its visible layers and anything that can't survive that
+//! (extra layers, blend modes, slices, ...) is listed in `Import::lost`.
+
+use std::io::{Read, Write};
+
+use crate::animation::{self, Animation, Tag};
+use crate::io::Error;
+use crate::sprite::{blend_over, Sprite};
+
+const FILE_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_OLD_PALETTE_64: u16 = 0x0011;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_CEL_EXTRA: u16 = 0x2006;
+const CHUNK_COLOR_PROFILE: u16 = 0x2007;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+const CHUNK_USER_DATA: u16 = 0x2020;
+const CHUNK_SLICE: u16 = 0x2022;
+const CHUNK_TILESET: u16 = 0x2023;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_BACKGROUND: u16 = 8;
+
+const LAYER_TYPE_GROUP: u16 = 1;
+const LAYER_TYPE_TILEMAP: u16 = 2;
+
+const CEL_RAW: u16 = 0;
+const CEL_LINKED: u16 = 1;
+const CEL_COMPRESSED: u16 = 2;
+
+/// Result of reading an Aseprite file.
+pub struct Import {
+    pub animation: Animation,
+    /// Human readable notes about data that was flattened or dropped.
+    pub lost: Vec<String>,
+}
+
+/// Aseprite files start with the file size followed by a fixed magic number.
+pub fn is_aseprite(data: &[u8]) -> bool {
+    data.len() >= 6 && u16::from_le_bytes([data[4], data[5]]) == FILE_MAGIC
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| corrupt("unexpected end of data"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), Error> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+}
+
+struct Layer {
+    name: String,
+    visible: bool,
+    background: bool,
+    kind: u16,
+    blend_mode: u16,
+    opacity: u8,
+}
+
+struct Cel {
+    layer: usize,
+    x: i32,
+    y: i32,
+    opacity: u8,
+    width: u32,
+    height: u32,
+    /// Pixels in the file's color depth, row-major
+    pixels: Vec<u8>,
+}
+
+struct Frame {
+    duration_ms: u16,
+    cels: Vec<Cel>,
+}
+
+/// Notes are deduplicated, since most chunk kinds repeat every frame.
+fn note(lost: &mut Vec<String>, message: impl Into<String>) {
+    let message = message.into();
+    if !lost.contains(&message) {
+        lost.push(message);
+    }
+}
+
+fn corrupt(detail: impl std::fmt::Display) -> Error {
+    Error::corrupt("Aseprite", detail)
+}
+
+pub fn read(data: &[u8]) -> Result<Import, Error> {
+    let mut r = Reader::new(data);
+    let _file_size = r.u32()?;
+    if r.u16()? != FILE_MAGIC {
+        return Err(Error::UnsupportedFormat);
+    }
+    let frame_count = r.u16()? as usize;
+    let width = r.u16()? as u32;
+    let height = r.u16()? as u32;
+    let depth = r.u16()?;
+    let flags = r.u32()?;
+    let _speed = r.u16()?;
+    r.skip(8)?;
+    let transparent_index = r.u8()?;
+    r.skip(3)?;
+    let _color_count = r.u16()?;
+    r.skip(2 + 4 + 4 + 84)?; // pixel ratio, grid position, grid size, reserved
+
+    if width == 0 || height == 0 {
+        return Err(corrupt("the canvas is empty"));
+    }
+    let bytes_per_pixel = match depth {
+        32 => 4,
+        16 => 2,
+        8 => 1,
+        _ => return Err(corrupt(format!("unsupported color depth {depth}"))),
+    };
+    // Every frame becomes a `Sprite` of the canvas size
+    Error::check_dimensions(width, height)?;
+    let layer_opacity_valid = flags & 1 != 0;
+
+    let mut layers: Vec<Layer> = Vec::new();
+    // Child level of each layer, used to hide layers inside hidden groups
+    let mut layer_levels: Vec<u16> = Vec::new();
+    let mut palette: Vec<[u8; 4]> = vec![[0, 0, 0, 255]; 256];
+    let mut has_palette = false;
+    let mut tags: Vec<Tag> = Vec::new();
+    let mut reversed_tags = false;
+    let mut frames: Vec<Frame> = Vec::with_capacity(frame_count);
+    let mut lost = Vec::new();
+
+    for _ in 0..frame_count {
+        let frame_start = r.pos;
+        let frame_size = r.u32()? as usize;
+        if r.u16()? != FRAME_MAGIC {
+            return Err(corrupt("bad frame header"));
+        }
+        let old_chunks = r.u16()? as u32;
+        let duration_ms = r.u16()?;
+        r.skip(2)?;
+        let new_chunks = r.u32()?;
+        let chunk_count = if new_chunks == 0 { old_chunks } else { new_chunks };
+
+        let mut frame = Frame {
+            duration_ms,
+            cels: Vec::new(),
+        };
+
+        for _ in 0..chunk_count {
+            let chunk_start = r.pos;
+            let chunk_size = r.u32()? as usize;
+            let chunk_type = r.u16()?;
+            if chunk_size < 6 {
+                return Err(corrupt("bad chunk size"));
+            }
+            let mut c = Reader::new(r.bytes(chunk_size - 6)?);
+
+            match chunk_type {
+                CHUNK_LAYER => {
+                    let layer_flags = c.u16()?;
+                    let kind = c.u16()?;
+                    let level = c.u16()?;
+                    c.skip(4)?; // default width and height, unused
+                    let blend_mode = c.u16()?;
+                    let opacity = c.u8()?;
+                    c.skip(3)?;
+                    let name = c.string()?;
+
+                    // Visible only if every enclosing group is visible too
+                    let mut visible = layer_flags & LAYER_VISIBLE != 0;
+                    let mut want = level;
+                    for (parent, &parent_level) in layers.iter().zip(&layer_levels).rev() {
+                        if want == 0 {
+                            break;
+                        }
+                        if parent_level == want - 1 {
+                            visible &= parent.visible;
+                            want -= 1;
+                        }
+                    }
+                    layers.push(Layer {
+                        name,
+                        visible,
+                        background: layer_flags & LAYER_BACKGROUND != 0,
+                        kind,
+                        blend_mode,
+                        opacity: if layer_opacity_valid { opacity } else { 255 },
+                    });
+                    layer_levels.push(level);
+                }
+                CHUNK_CEL => {
+                    let layer = c.u16()? as usize;
+                    let x = c.i16()? as i32;
+                    let y = c.i16()? as i32;
+                    let opacity = c.u8()?;
+                    let cel_type = c.u16()?;
+                    c.skip(2 + 5)?; // z-index, reserved
+                    match cel_type {
+                        CEL_RAW | CEL_COMPRESSED => {
+                            let w = c.u16()? as u32;
+                            let h = c.u16()? as u32;
+                            // Compressed data can inflate far past its chunk, so
+                            // bound the size before allocating. Cels may reach
+                            // past the canvas and are cropped when composited.
+                            Error::check_dimensions(w, h)?;
+                            let len = (w * h) as usize * bytes_per_pixel;
+                            let pixels = if cel_type == CEL_RAW {
+                                c.bytes(len)?.to_vec()
+                            } else {
+                                let mut out = Vec::with_capacity(len);
+                                flate2::read::ZlibDecoder::new(&c.data[c.pos..])
+                                    .take(len as u64)
+                                    .read_to_end(&mut out)
+                                    .map_err(|e| corrupt(format!("cel data: {e}")))?;
+                                out
+                            };
+                            if pixels.len() != len {
+                                return Err(corrupt("truncated cel data"));
+                            }
+                            frame.cels.push(Cel {
+                                layer,
+                                x,
+                                y,
+                                opacity,
+                                width: w,
+                                height: h,
+                                pixels,
+                            });
+                        }
+                        CEL_LINKED => {
+                            let source = c.u16()? as usize;
+                            let linked = frames
+                                .get(source)
+                                .and_then(|f| f.cels.iter().find(|cel| cel.layer == layer))
+                                .ok_or_else(|| corrupt("a cel links to a missing frame"))?;
+                            frame.cels.push(Cel {
+                                layer,
+                                x,
+                                y,
+                                opacity,
+                                width: linked.width,
+                                height: linked.height,
+                                pixels: linked.pixels.clone(),
+                            });
+                        }
+                        _ => note(&mut lost, "Tilemap cels were dropped"),
+                    }
+                }
+                CHUNK_PALETTE => {
+                    let size = c.u32()? as usize;
+                    let first = c.u32()? as usize;
+                    let last = c.u32()? as usize;
+                    c.skip(8)?;
+                    if size > 256 {
+                        return Err(corrupt(format!("the palette has {size} colors, over 256")));
+                    }
+                    for index in first..=last {
+                        let entry_flags = c.u16()?;
+                        let rgba = [c.u8()?, c.u8()?, c.u8()?, c.u8()?];
+                        if entry_flags & 1 != 0 {
+                            c.string()?;
+                        }
+                        if let Some(slot) = palette.get_mut(index) {
+                            *slot = rgba;
+                        }
+                    }
+                    has_palette = true;
+                }
+                CHUNK_OLD_PALETTE | CHUNK_OLD_PALETTE_64 => {
+                    // Only used when the newer palette chunk is absent
+                    if has_palette {
+                        continue;
+                    }
+                    let scale = |v: u8| {
+                        if chunk_type == CHUNK_OLD_PALETTE_64 {
+                            (v as u32 * 255 / 63) as u8
+                        } else {
+                            v
+                        }
+                    };
+                    let packets = c.u16()?;
+                    let mut index = 0usize;
+                    for _ in 0..packets {
+                        index += c.u8()? as usize;
+                        let count = match c.u8()? {
+                            0 => 256,
+                            n => n as usize,
+                        };
+                        for _ in 0..count {
+                            let rgba = [scale(c.u8()?), scale(c.u8()?), scale(c.u8()?), 255];
+                            if let Some(slot) = palette.get_mut(index) {
+                                *slot = rgba;
+                            }
+                            index += 1;
+                        }
+                    }
+                }
+                CHUNK_TAGS => {
+                    let count = c.u16()?;
+                    c.skip(8)?;
+                    for _ in 0..count {
+                        let from = c.u16()? as usize;
+                        let to = c.u16()? as usize;
+                        // 0 forward, 1 reverse, 2 ping-pong, 3 ping-pong reverse
+                        reversed_tags |= c.u8()? != 0;
+                        let repeat = c.u16()?;
+                        c.skip(6 + 3 + 1)?;
+                        tags.push(Tag {
+                            name: c.string()?,
+                            from,
+                            to,
+                            looping: repeat == 0,
+                        });
+                    }
+                }
+                CHUNK_SLICE => note(&mut lost, "Slices were dropped"),
+                CHUNK_USER_DATA => note(&mut lost, "User data was dropped"),
+                CHUNK_TILESET => note(&mut lost, "Tilesets were dropped"),
+                CHUNK_CEL_EXTRA | CHUNK_COLOR_PROFILE => {}
+                _ => {}
+            }
+
+            r.pos = chunk_start + chunk_size;
+        }
+
+        frames.push(frame);
+        if frame_size > 0 {
+            r.pos = frame_start + frame_size;
+        }
+    }
+
+    // Convert a pixel in the file's color depth to RGBA
+    let to_rgba = |px: &[u8], background: bool| -> [u8; 4] {
+        match bytes_per_pixel {
+            4 => [px[0], px[1], px[2], px[3]],
+            2 => [px[0], px[0], px[0], px[1]],
+            _ => {
+                let index = px[0];
+                if index == transparent_index && !background {
+                    [0, 0, 0, 0]
+                } else {
+                    palette.get(index as usize).copied().unwrap_or([0, 0, 0, 255])
+                }
+            }
+        }
+    };
+
+    let mut composited: Vec<animation::Frame> = Vec::with_capacity(frames.len());
+    for frame in &frames {
+        let mut sprite = Sprite::new(width, height);
+        let mut cels: Vec<&Cel> = frame.cels.iter().collect();
+        cels.sort_by_key(|cel| cel.layer);
+        for cel in cels {
+            let Some(layer) = layers.get(cel.layer) else {
+                continue;
+            };
+            if !layer.visible || layer.kind == LAYER_TYPE_GROUP {
+                continue;
+            }
+            let opacity = cel.opacity as u32 * layer.opacity as u32 / 255;
+            if cel.x < 0
+                || cel.y < 0
+                || cel.x as i64 + cel.width as i64 > width as i64
+                || cel.y as i64 + cel.height as i64 > height as i64
+            {
+                note(&mut lost, "Cels reaching past the canvas were cropped");
+            }
+            for cy in 0..cel.height {
+                for cx in 0..cel.width {
+                    let x = cel.x + cx as i32;
+                    let y = cel.y + cy as i32;
+                    if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                        continue;
+                    }
+                    let i = (cy * cel.width + cx) as usize * bytes_per_pixel;
+                    let mut src = to_rgba(&cel.pixels[i..i + bytes_per_pixel], layer.background);
+                    src[3] = (src[3] as u32 * opacity / 255) as u8;
+                    let dst = sprite.get_pixel(x as u32, y as u32);
+                    sprite.set_pixel(x as u32, y as u32, blend_over(src, dst));
+                }
+            }
+        }
+        composited.push(animation::Frame {
+            sprite,
+            duration_ms: match frame.duration_ms {
+                0 => animation::DEFAULT_FRAME_DURATION_MS,
+                ms => ms as u32,
+            },
+        });
+    }
+
+    let visible_layers: Vec<&Layer> = layers
+        .iter()
+        .filter(|l| l.visible && l.kind != LAYER_TYPE_GROUP)
+        .collect();
+    if visible_layers.len() > 1 {
+        note(
+            &mut lost,
+            format!("{} layers were flattened into one", visible_layers.len()),
+        );
+    }
+    let hidden: Vec<&str> = layers
+        .iter()
+        .filter(|l| !l.visible && l.kind != LAYER_TYPE_GROUP)
+        .map(|l| l.name.as_str())
+        .collect();
+    if !hidden.is_empty() {
+        note(&mut lost, format!("Hidden layers were dropped: {}", hidden.join(", ")));
+    }
+    if visible_layers.iter().any(|l| l.blend_mode != 0) {
+        note(&mut lost, "Layer blend modes were approximated as Normal");
+    }
+    if layers.iter().any(|l| l.kind == LAYER_TYPE_TILEMAP) {
+        note(&mut lost, "Tilemap layers were dropped");
+    }
+    if reversed_tags {
+        note(&mut lost, "Reverse and ping-pong tags play forward");
+    }
+    if depth == 8 {
+        note(&mut lost, "The indexed palette was converted to RGBA");
+    }
+
+    if composited.is_empty() {
+        return Err(corrupt("no frames"));
+    }
+    let frame_count = composited.len();
+    let mut animation = Animation::from_frames(composited);
+    animation.tags = tags
+        .into_iter()
+        .filter(|tag| tag.from <= tag.to && tag.to < frame_count)
+        .collect();
+    Ok(Import { animation, lost })
+}
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn zeros(&mut self, len: usize) {
+        self.buf.resize(self.buf.len() + len, 0);
+    }
+
+    fn string(&mut self, s: &str) {
+        self.u16(s.len() as u16);
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn chunk(&mut self, chunk_type: u16, body: &[u8]) {
+        self.u32(body.len() as u32 + 6);
+        self.u16(chunk_type);
+        self.buf.extend_from_slice(body);
+    }
+}
+
+/// Distinct colors across all frames in order of first appearance, if there
+/// are at most 256 of them.
+fn animation_palette(animation: &Animation) -> Option<Vec<[u8; 4]>> {
+    let mut colors: Vec<[u8; 4]> = Vec::new();
+    for frame in &animation.frames {
+        for px in frame.sprite.pixels.chunks_exact(4) {
+            let color = [px[0], px[1], px[2], px[3]];
+            if !colors.contains(&color) {
+                if colors.len() == 256 {
+                    return None;
+                }
+                colors.push(color);
+            }
+        }
+    }
+    Some(colors)
+}
+
+fn compressed_cel(sprite: &Sprite) -> Vec<u8> {
+    let mut cel = Writer { buf: Vec::new() };
+    cel.u16(0); // layer index
+    cel.u16(0); // x
+    cel.u16(0); // y
+    cel.u8(255);
+    cel.u16(CEL_COMPRESSED);
+    cel.u16(0); // z-index
+    cel.zeros(5);
+    cel.u16(sprite.width as u16);
+    cel.u16(sprite.height as u16);
+    let mut encoder =
+        flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(&sprite.pixels)
+        .expect("Writing to a Vec cannot fail");
+    cel.buf
+        .extend_from_slice(&encoder.finish().expect("Writing to a Vec cannot fail"));
+    cel.buf
+}
+
+/// Encode the animation as a single-layer RGBA Aseprite file, one frame per
+/// animation frame. Fails when a size or count doesn't fit the format's
+/// 16-bit fields.
+pub fn write(animation: &Animation) -> Result<Vec<u8>, Error> {
+    let field = |value: usize, what: &str| {
+        u16::try_from(value).map_err(|_| Error::Encode(format!("{what} for Aseprite")))
+    };
+    let frame_count = field(animation.frames.len(), "too many frames")?;
+    let width = field(animation.width() as usize, "sprite too wide")?;
+    let height = field(animation.height() as usize, "sprite too tall")?;
+    let tag_count = field(animation.tags.len(), "too many tags")?;
+
+    let mut out = Writer { buf: Vec::new() };
+    // File header
+    out.u32(0); // file size, patched below
+    out.u16(FILE_MAGIC);
+    out.u16(frame_count);
+    out.u16(width);
+    out.u16(height);
+    out.u16(32); // RGBA
+    out.u32(1); // layer opacity is valid
+    out.u16(100); // deprecated speed
+    out.zeros(8);
+    out.u8(0); // transparent index
+    out.zeros(3);
+    out.u16(0); // color count (256)
+    out.u8(1); // pixel width
+    out.u8(1); // pixel height
+    out.u16(0); // grid x
+    out.u16(0); // grid y
+    out.u16(16); // grid width
+    out.u16(16); // grid height
+    out.zeros(84);
+
+    for (index, frame) in animation.frames.iter().enumerate() {
+        let mut chunks: Vec<(u16, Vec<u8>)> = Vec::new();
+
+        if index == 0 {
+            // Palette of the colors in use, so they show up in Aseprite's swatches
+            if let Some(colors) = animation_palette(animation).filter(|c| !c.is_empty()) {
+                let mut body = Writer { buf: Vec::new() };
+                body.u32(colors.len() as u32);
+                body.u32(0);
+                body.u32(colors.len() as u32 - 1);
+                body.zeros(8);
+                for [r, g, b, a] in colors {
+                    body.u16(0);
+                    body.buf.extend_from_slice(&[r, g, b, a]);
+                }
+                chunks.push((CHUNK_PALETTE, body.buf));
+            }
+
+            let mut layer = Writer { buf: Vec::new() };
+            layer.u16(LAYER_VISIBLE | 2); // visible, editable
+            layer.u16(0); // normal layer
+            layer.u16(0); // child level
+            layer.u16(0);
+            layer.u16(0);
+            layer.u16(0); // blend mode: normal
+            layer.u8(255);
+            layer.zeros(3);
+            layer.string("Layer 1");
+            chunks.push((CHUNK_LAYER, layer.buf));
+        }
+
+        if index == 0 && !animation.tags.is_empty() {
+            let mut tags = Writer { buf: Vec::new() };
+            tags.u16(tag_count);
+            tags.zeros(8);
+            for tag in &animation.tags {
+                let past_end = format!("tag \"{}\" out of range", tag.name);
+                tags.u16(field(tag.from, &past_end)?);
+                tags.u16(field(tag.to, &past_end)?);
+                tags.u8(0); // forward
+                tags.u16(if tag.looping { 0 } else { 1 });
+                tags.zeros(6);
+                tags.zeros(3); // deprecated color
+                tags.u8(0);
+                tags.string(&tag.name);
+            }
+            chunks.push((CHUNK_TAGS, tags.buf));
+        }
+
+        chunks.push((CHUNK_CEL, compressed_cel(&frame.sprite)));
+
+        let mut body = Writer { buf: Vec::new() };
+        for (chunk_type, chunk) in &chunks {
+            body.chunk(*chunk_type, chunk);
+        }
+
+        // Frame header
+        out.u32(16 + body.buf.len() as u32);
+        out.u16(FRAME_MAGIC);
+        out.u16(chunks.len().min(0xFFFF) as u16);
+        out.u16(frame.duration_ms.min(u16::MAX as u32) as u16);
+        out.zeros(2);
+        out.u32(chunks.len() as u32);
+        out.buf.extend_from_slice(&body.buf);
+    }
+
+    let size = out.buf.len() as u32;
+    out.buf[0..4].copy_from_slice(&size.to_le_bytes());
+    Ok(out.buf)
+}
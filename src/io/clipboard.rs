@@ -0,0 +1,139 @@
+//! Image copy and paste through the system clipboard: arboard natively,
+//! the async Clipboard API in the browser.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native {
+    use std::borrow::Cow;
+
+    use crate::io::Error;
+    use crate::sprite::Sprite;
+
+    /// Handle to the OS clipboard, opened on first use. It stays open
+    /// because on X11 copied data only lives as long as its owner.
+    #[derive(Default)]
+    pub struct Clipboard {
+        inner: Option<arboard::Clipboard>,
+    }
+
+    impl Clipboard {
+        fn get(&mut self) -> Result<&mut arboard::Clipboard, Error> {
+            if self.inner.is_none() {
+                let clipboard =
+                    arboard::Clipboard::new().map_err(|e| Error::Clipboard(e.to_string()))?;
+                self.inner = Some(clipboard);
+            }
+            Ok(self.inner.as_mut().unwrap())
+        }
+
+        pub fn copy(&mut self, sprite: &Sprite) -> Result<(), Error> {
+            let image = arboard::ImageData {
+                width: sprite.width as usize,
+                height: sprite.height as usize,
+                bytes: Cow::Borrowed(&sprite.pixels),
+            };
+            self.get()?
+                .set_image(image)
+                .map_err(|e| Error::Clipboard(e.to_string()))
+        }
+
+        pub fn paste(&mut self) -> Result<Sprite, Error> {
+            let image = self.get()?.get_image().map_err(|e| match e {
+                arboard::Error::ContentNotAvailable => {
+                    Error::Clipboard("no image on the clipboard".into())
+                }
+                e => Error::Clipboard(e.to_string()),
+            })?;
+            Ok(Sprite {
+                width: image.width as u32,
+                height: image.height as u32,
+                pixels: image.bytes.into_owned(),
+            })
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub mod web {
+    use std::cell::RefCell;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    use crate::io::web::{blob, js_message};
+    use crate::io::{decode_image, sprite_to_png, Error};
+    use crate::sprite::Sprite;
+
+    thread_local! {
+        static PENDING: RefCell<Option<Result<Sprite, Error>>> = RefCell::new(None);
+    }
+
+    fn set_pending(result: Result<Sprite, Error>) {
+        PENDING.with(|p| *p.borrow_mut() = Some(result));
+    }
+
+    fn clipboard() -> Result<web_sys::Clipboard, Error> {
+        web_sys::window()
+            .map(|window| window.navigator().clipboard())
+            .ok_or_else(|| Error::Clipboard("no window".into()))
+    }
+
+    /// Start writing `sprite` to the clipboard as a PNG. Browsers only
+    /// allow this during a user action; a refusal lands in the pending slot.
+    pub fn copy(sprite: &Sprite) -> Result<(), Error> {
+        let png = sprite_to_png(sprite)?;
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = write(&png).await {
+                set_pending(Err(e));
+            }
+        });
+        Ok(())
+    }
+
+    async fn write(png: &[u8]) -> Result<(), Error> {
+        let record = js_sys::Object::new();
+        js_sys::Reflect::set(&record, &"image/png".into(), &blob(png, "image/png")?)?;
+        let item = web_sys::ClipboardItem::new_with_record_from_str_to_blob_promise(&record)?;
+        JsFuture::from(clipboard()?.write(&js_sys::Array::of1(&item)))
+            .await
+            .map_err(|e| Error::Clipboard(js_message(&e)))?;
+        Ok(())
+    }
+
+    /// Start reading an image from the clipboard; it arrives through
+    /// `check_pending`. The browser may ask the user for permission first.
+    pub fn request_paste() {
+        wasm_bindgen_futures::spawn_local(async {
+            set_pending(read().await);
+        });
+    }
+
+    async fn read() -> Result<Sprite, Error> {
+        let items = JsFuture::from(clipboard()?.read())
+            .await
+            .map_err(|e| Error::Clipboard(js_message(&e)))?;
+        let items: js_sys::Array = items.unchecked_into();
+        for item in items.iter() {
+            let item: web_sys::ClipboardItem = item.unchecked_into();
+            let image_type = item
+                .types()
+                .iter()
+                .filter_map(|t| t.as_string())
+                .find(|t| t.starts_with("image/"));
+            let Some(image_type) = image_type else {
+                continue;
+            };
+            let blob: web_sys::Blob = JsFuture::from(item.get_type(&image_type))
+                .await
+                .map_err(|e| Error::Clipboard(js_message(&e)))?
+                .unchecked_into();
+            let buffer = JsFuture::from(blob.array_buffer())
+                .await
+                .map_err(|e| Error::Clipboard(js_message(&e)))?;
+            return decode_image(&js_sys::Uint8Array::new(&buffer).to_vec());
+        }
+        Err(Error::Clipboard("no image on the clipboard".into()))
+    }
+
+    pub fn check_pending() -> Option<Result<Sprite, Error>> {
+        PENDING.with(|p| p.borrow_mut().take())
+    }
+}
@@ -0,0 +1,367 @@
+//! Pixel data as source code (C header, Rust `const` array) or a raw binary
+//! blob, for embedded and fantasy-console targets.
+
+use std::fmt::Write;
+
+use crate::animation::Animation;
+use crate::io::Error;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PixelFormat {
+    Rgba8888,
+    Rgb565,
+    Argb4444,
+    /// One byte per pixel indexing a palette of up to 256 RGBA colors
+    Indexed8,
+}
+
+impl PixelFormat {
+    pub const ALL: [PixelFormat; 4] = [
+        PixelFormat::Rgba8888,
+        PixelFormat::Rgb565,
+        PixelFormat::Argb4444,
+        PixelFormat::Indexed8,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PixelFormat::Rgba8888 => "RGBA8888",
+            PixelFormat::Rgb565 => "RGB565",
+            PixelFormat::Argb4444 => "ARGB4444",
+            PixelFormat::Indexed8 => "Indexed 8-bit",
+        }
+    }
+
+    pub fn bytes_per_pixel(&self) -> u32 {
+        match self {
+            PixelFormat::Rgba8888 => 4,
+            PixelFormat::Rgb565 | PixelFormat::Argb4444 => 2,
+            PixelFormat::Indexed8 => 1,
+        }
+    }
+
+    fn is_16_bit(&self) -> bool {
+        self.bytes_per_pixel() == 2
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CodeOutput {
+    CHeader,
+    RustConst,
+    RawBinary,
+}
+
+impl CodeOutput {
+    pub const ALL: [CodeOutput; 3] = [
+        CodeOutput::CHeader,
+        CodeOutput::RustConst,
+        CodeOutput::RawBinary,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CodeOutput::CHeader => "C header",
+            CodeOutput::RustConst => "Rust const",
+            CodeOutput::RawBinary => "Raw binary",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CodeOutput::CHeader => "h",
+            CodeOutput::RustConst => "rs",
+            CodeOutput::RawBinary => "bin",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CodeOptions {
+    pub format: PixelFormat,
+    pub output: CodeOutput,
+    /// Rows are padded with zeros to a multiple of this many bytes
+    pub row_align: u32,
+    /// Byte order of 16-bit pixels in raw binaries
+    pub big_endian: bool,
+    /// Base name for the generated symbols
+    pub name: String,
+}
+
+impl Default for CodeOptions {
+    fn default() -> Self {
+        Self {
+            format: PixelFormat::Rgba8888,
+            output: CodeOutput::CHeader,
+            row_align: 1,
+            big_endian: false,
+            name: "sprite".into(),
+        }
+    }
+}
+
+pub struct CodeExport {
+    pub data: Vec<u8>,
+    /// RGBA palette for indexed raw binaries, saved next to the pixels.
+    /// Source code outputs carry their palette inline.
+    pub palette: Option<Vec<u8>>,
+}
+
+/// Bytes per row once padded to `row_align`.
+pub fn row_stride(width: u32, options: &CodeOptions) -> u32 {
+    let align = options.row_align.max(1);
+    (width * options.format.bytes_per_pixel()).div_ceil(align) * align
+}
+
+/// Encode every frame, one after another, in the chosen format and output.
+pub fn export(animation: &Animation, options: &CodeOptions) -> Result<CodeExport, Error> {
+    let palette = match options.format {
+        PixelFormat::Indexed8 => Some(palette(animation)?),
+        _ => None,
+    };
+    let width = animation.width();
+    let stride = row_stride(width, options);
+
+    // Pixels as 8 or 16-bit values, rows padded with zeros
+    let mut values: Vec<u16> = Vec::new();
+    let per_value = if options.format.is_16_bit() { 2 } else { 1 };
+    let row_values = (stride / per_value) as usize;
+    for frame in &animation.frames {
+        for y in 0..frame.sprite.height {
+            let start = values.len();
+            for x in 0..width {
+                encode_pixel(
+                    &mut values,
+                    frame.sprite.get_pixel(x, y),
+                    options.format,
+                    &palette,
+                );
+            }
+            values.resize(start + row_values, 0);
+        }
+    }
+
+    let data = match options.output {
+        CodeOutput::RawBinary => {
+            let mut bytes = Vec::with_capacity(values.len() * per_value as usize);
+            for v in &values {
+                match (options.format.is_16_bit(), options.big_endian) {
+                    (false, _) => bytes.push(*v as u8),
+                    (true, false) => bytes.extend_from_slice(&v.to_le_bytes()),
+                    (true, true) => bytes.extend_from_slice(&v.to_be_bytes()),
+                }
+            }
+            bytes
+        }
+        CodeOutput::CHeader => c_header(animation, options, stride, &values, &palette).into_bytes(),
+        CodeOutput::RustConst => {
+            rust_const(animation, options, stride, &values, &palette).into_bytes()
+        }
+    };
+    let palette = match options.output {
+        CodeOutput::RawBinary => palette.map(|colors| colors.concat()),
+        _ => None,
+    };
+    Ok(CodeExport { data, palette })
+}
+
+fn encode_pixel(
+    out: &mut Vec<u16>,
+    [r, g, b, a]: [u8; 4],
+    format: PixelFormat,
+    palette: &Option<Vec<[u8; 4]>>,
+) {
+    match format {
+        PixelFormat::Rgba8888 => out.extend([r, g, b, a].map(u16::from)),
+        PixelFormat::Rgb565 => {
+            out.push(((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3));
+        }
+        PixelFormat::Argb4444 => {
+            out.push(
+                ((a as u16 >> 4) << 12)
+                    | ((r as u16 >> 4) << 8)
+                    | ((g as u16 >> 4) << 4)
+                    | (b as u16 >> 4),
+            );
+        }
+        PixelFormat::Indexed8 => {
+            let colors = palette.as_ref().expect("Indexed export needs a palette");
+            let index = colors.iter().position(|&c| c == [r, g, b, a]).unwrap_or(0);
+            out.push(index as u16);
+        }
+    }
+}
+
+/// Colors used across all frames, fully transparent pixels collapsed to
+/// index 0.
+fn palette(animation: &Animation) -> Result<Vec<[u8; 4]>, Error> {
+    let mut colors = vec![[0, 0, 0, 0]];
+    for frame in &animation.frames {
+        for px in frame.sprite.pixels.chunks_exact(4) {
+            let color = if px[3] == 0 {
+                [0, 0, 0, 0]
+            } else {
+                [px[0], px[1], px[2], px[3]]
+            };
+            if !colors.contains(&color) {
+                if colors.len() == 256 {
+                    let detail = "too many colors for an indexed export (max 256)";
+                    return Err(Error::Encode(detail.into()));
+                }
+                colors.push(color);
+            }
+        }
+    }
+    Ok(colors)
+}
+
+/// Turn a file name into a C/Rust identifier.
+pub fn identifier(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if id.is_empty() || id.starts_with(|c: char| c.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    id
+}
+
+fn summary(animation: &Animation, options: &CodeOptions, stride: u32) -> String {
+    format!(
+        "Generated by Spritedit: {}x{}, {} frame(s), {}, {} bytes per row",
+        animation.width(),
+        animation.height(),
+        animation.frames.len(),
+        options.format.name(),
+        stride
+    )
+}
+
+/// Comma-separated values, sixteen to a line.
+fn value_lines(values: impl Iterator<Item = String>, indent: &str) -> String {
+    let values: Vec<String> = values.collect();
+    values
+        .chunks(16)
+        .map(|line| format!("{indent}{},", line.join(", ")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn hex_values(values: &[u16], sixteen_bit: bool) -> impl Iterator<Item = String> + '_ {
+    values.iter().map(move |v| {
+        if sixteen_bit {
+            format!("0x{v:04X}")
+        } else {
+            format!("0x{v:02X}")
+        }
+    })
+}
+
+fn c_header(
+    animation: &Animation,
+    options: &CodeOptions,
+    stride: u32,
+    values: &[u16],
+    palette: &Option<Vec<[u8; 4]>>,
+) -> String {
+    let id = identifier(&options.name);
+    let upper = id.to_ascii_uppercase();
+    let sixteen = options.format.is_16_bit();
+    let c_type = if sixteen { "uint16_t" } else { "uint8_t" };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "// {}", summary(animation, options, stride));
+    let _ = writeln!(out, "#ifndef {upper}_H");
+    let _ = writeln!(out, "#define {upper}_H");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#include <stdint.h>");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#define {upper}_WIDTH {}", animation.width());
+    let _ = writeln!(out, "#define {upper}_HEIGHT {}", animation.height());
+    let _ = writeln!(out, "#define {upper}_FRAMES {}", animation.frames.len());
+    let _ = writeln!(out, "#define {upper}_STRIDE {stride}");
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "static const {c_type} {id}_pixels[{}] = {{",
+        values.len()
+    );
+    let _ = writeln!(out, "{}", value_lines(hex_values(values, sixteen), "    "));
+    let _ = writeln!(out, "}};");
+    if let Some(colors) = palette {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "#define {upper}_PALETTE_SIZE {}", colors.len());
+        let _ = writeln!(out, "// RGBA, 4 bytes per color");
+        let _ = writeln!(
+            out,
+            "static const uint8_t {id}_palette[{}] = {{",
+            colors.len() * 4
+        );
+        let bytes: Vec<u16> = colors.iter().flatten().map(|&b| b as u16).collect();
+        let _ = writeln!(out, "{}", value_lines(hex_values(&bytes, false), "    "));
+        let _ = writeln!(out, "}};");
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#endif // {upper}_H");
+    out
+}
+
+fn rust_const(
+    animation: &Animation,
+    options: &CodeOptions,
+    stride: u32,
+    values: &[u16],
+    palette: &Option<Vec<[u8; 4]>>,
+) -> String {
+    let upper = identifier(&options.name).to_ascii_uppercase();
+    let sixteen = options.format.is_16_bit();
+    let rust_type = if sixteen { "u16" } else { "u8" };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "// {}", summary(animation, options, stride));
+    let _ = writeln!(
+        out,
+        "pub const {upper}_WIDTH: usize = {};",
+        animation.width()
+    );
+    let _ = writeln!(
+        out,
+        "pub const {upper}_HEIGHT: usize = {};",
+        animation.height()
+    );
+    let _ = writeln!(
+        out,
+        "pub const {upper}_FRAMES: usize = {};",
+        animation.frames.len()
+    );
+    let _ = writeln!(out, "pub const {upper}_STRIDE: usize = {stride};");
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "pub const {upper}_PIXELS: [{rust_type}; {}] = [",
+        values.len()
+    );
+    let _ = writeln!(out, "{}", value_lines(hex_values(values, sixteen), "    "));
+    let _ = writeln!(out, "];");
+    if let Some(colors) = palette {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "/// RGBA colors indexed by `{upper}_PIXELS`");
+        let _ = writeln!(
+            out,
+            "pub const {upper}_PALETTE: [[u8; 4]; {}] = [",
+            colors.len()
+        );
+        for [r, g, b, a] in colors {
+            let _ = writeln!(out, "    [0x{r:02X}, 0x{g:02X}, 0x{b:02X}, 0x{a:02X}],");
+        }
+        let _ = writeln!(out, "];");
+    }
+    out
+}
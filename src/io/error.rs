@@ -0,0 +1,101 @@
+use std::fmt;
+
+/// Largest file we'll load, so a stray video or disk image can't exhaust
+/// memory (or the browser tab).
+pub const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Most pixels an image or layer may have once decoded, since a small file
+/// can claim a huge canvas: 8192x8192, 256 MB as RGBA.
+pub const MAX_PIXELS: u32 = 8192 * 8192;
+
+/// Why loading or saving a file failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The data isn't in any format we can read
+    UnsupportedFormat,
+    /// The format was recognized but the data doesn't decode
+    Corrupt { format: String, detail: String },
+    /// Larger than `MAX_FILE_SIZE`
+    TooLarge,
+    /// Its dimensions come to more than `MAX_PIXELS`
+    ImageTooLarge { width: u32, height: u32 },
+    PermissionDenied { path: String },
+    /// Any other file system failure
+    File(String),
+    /// The request never got a response
+    Network(String),
+    /// The server answered with a non-success status
+    HttpStatus(u16),
+    /// The image couldn't be encoded
+    Encode(String),
+    /// Reading or writing the system clipboard failed
+    Clipboard(String),
+    /// A browser API call failed
+    #[cfg(target_arch = "wasm32")]
+    Browser(String),
+}
+
+impl Error {
+    pub fn corrupt(format: &str, detail: impl fmt::Display) -> Self {
+        Error::Corrupt {
+            format: format.into(),
+            detail: detail.to_string(),
+        }
+    }
+
+    /// Classify a file system error on `path`.
+    pub fn from_io(error: std::io::Error, path: &std::path::Path) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::PermissionDenied => Error::PermissionDenied {
+                path: path.display().to_string(),
+            },
+            _ => Error::File(format!("{}: {error}", path.display())),
+        }
+    }
+
+    pub fn check_size(bytes: u64) -> Result<(), Error> {
+        if bytes > MAX_FILE_SIZE {
+            Err(Error::TooLarge)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check an image's dimensions before allocating it. Within
+    /// `MAX_PIXELS`, its RGBA byte count also fits in a u32.
+    pub fn check_dimensions(width: u32, height: u32) -> Result<(), Error> {
+        match width.checked_mul(height) {
+            Some(pixels) if pixels <= MAX_PIXELS => Ok(()),
+            _ => Err(Error::ImageTooLarge { width, height }),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnsupportedFormat => write!(f, "Unsupported file format"),
+            Error::Corrupt { format, detail } => write!(f, "Corrupt {format} file: {detail}"),
+            Error::TooLarge => write!(
+                f,
+                "File is larger than the {} MB limit",
+                MAX_FILE_SIZE / (1024 * 1024)
+            ),
+            Error::ImageTooLarge { width, height } => write!(
+                f,
+                "A {width}x{height} image is larger than the {} megapixel limit",
+                MAX_PIXELS / (1024 * 1024)
+            ),
+            Error::PermissionDenied { path } => write!(f, "Permission denied: {path}"),
+            Error::File(detail) => write!(f, "File error: {detail}"),
+            Error::Network(detail) => write!(f, "Network error: {detail}"),
+            Error::HttpStatus(code) => write!(f, "Server returned HTTP {code}"),
+            Error::Encode(detail) => write!(f, "Encoding failed: {detail}"),
+            Error::Clipboard(detail) => write!(f, "Clipboard error: {detail}"),
+            #[cfg(target_arch = "wasm32")]
+            Error::Browser(detail) => write!(f, "Browser error: {detail}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,157 @@
+//! Animated GIF import and export.
+
+use crate::animation::{Animation, Frame, DEFAULT_FRAME_DURATION_MS};
+use crate::io::Error;
+use crate::sprite::Sprite;
+
+/// How an animation is flattened into GIF's 256 colors and 1-bit alpha.
+#[derive(Clone)]
+pub struct GifOptions {
+    /// Pixels with alpha below this become the transparent color; the rest
+    /// are made fully opaque.
+    pub alpha_threshold: u8,
+    /// Blend partially transparent pixels onto this color instead of just
+    /// dropping their alpha.
+    pub matte: Option<[u8; 3]>,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            alpha_threshold: 128,
+            matte: None,
+        }
+    }
+}
+
+pub fn is_gif(data: &[u8]) -> bool {
+    data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")
+}
+
+pub fn write(animation: &Animation, options: &GifOptions) -> Result<Vec<u8>, Error> {
+    let width = u16::try_from(animation.width())
+        .map_err(|_| Error::Encode("sprite too wide for GIF".into()))?;
+    let height = u16::try_from(animation.height())
+        .map_err(|_| Error::Encode("sprite too tall for GIF".into()))?;
+
+    let mut buf = Vec::new();
+    {
+        let mut encoder = ::gif::Encoder::new(&mut buf, width, height, &[])
+            .map_err(|e| Error::Encode(e.to_string()))?;
+        // The NETSCAPE extension counts repeats after the first play, with 0
+        // meaning forever; leaving it out plays the animation once.
+        let repeat = match animation.loop_count {
+            0 => Some(::gif::Repeat::Infinite),
+            1 => None,
+            n => Some(::gif::Repeat::Finite(n - 1)),
+        };
+        if let Some(repeat) = repeat {
+            encoder
+                .set_repeat(repeat)
+                .map_err(|e| Error::Encode(e.to_string()))?;
+        }
+
+        for frame in &animation.frames {
+            let mut pixels = flatten_alpha(&frame.sprite, options);
+            let mut gif_frame = ::gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+            // GIF delays are in hundredths of a second
+            gif_frame.delay = (frame.duration_ms.div_ceil(10)).min(u16::MAX as u32) as u16;
+            gif_frame.dispose = ::gif::DisposalMethod::Background;
+            encoder
+                .write_frame(&gif_frame)
+                .map_err(|e| Error::Encode(e.to_string()))?;
+        }
+    }
+    Ok(buf)
+}
+
+/// Reduce a frame's alpha channel to the on/off transparency GIF supports.
+fn flatten_alpha(sprite: &Sprite, options: &GifOptions) -> Vec<u8> {
+    let mut pixels = sprite.pixels.clone();
+    for px in pixels.chunks_exact_mut(4) {
+        if px[3] < options.alpha_threshold {
+            px.copy_from_slice(&[0, 0, 0, 0]);
+            continue;
+        }
+        if let Some(matte) = options.matte {
+            let a = px[3] as u32;
+            for (c, m) in px[..3].iter_mut().zip(matte) {
+                *c = ((*c as u32 * a + m as u32 * (255 - a)) / 255) as u8;
+            }
+        }
+        px[3] = 255;
+    }
+    pixels
+}
+
+/// Decode every GIF frame into a full-size RGBA frame, applying each frame's
+/// offset and disposal so the result matches what a browser would show.
+pub fn read(data: &[u8]) -> Result<Animation, Error> {
+    let mut options = ::gif::DecodeOptions::new();
+    options.set_color_output(::gif::ColorOutput::RGBA);
+    let mut decoder = options
+        .read_info(data)
+        .map_err(|e| Error::corrupt("GIF", e))?;
+
+    let width = decoder.width() as u32;
+    let height = decoder.height() as u32;
+    // The logical screen size is taken on trust, so check it before
+    // allocating the canvas
+    Error::check_dimensions(width, height)?;
+    let mut canvas = Sprite::new(width, height);
+    let mut frames = Vec::new();
+
+    while let Some(frame) = decoder
+        .read_next_frame()
+        .map_err(|e| Error::corrupt("GIF", e))?
+    {
+        let previous = (frame.dispose == ::gif::DisposalMethod::Previous).then(|| canvas.clone());
+        let (left, top) = (frame.left as u32, frame.top as u32);
+        let (fw, fh) = (frame.width as u32, frame.height as u32);
+
+        for y in 0..fh {
+            for x in 0..fw {
+                let i = ((y * fw + x) * 4) as usize;
+                let px = &frame.buffer[i..i + 4];
+                if px[3] > 0 {
+                    canvas.set_pixel(left + x, top + y, [px[0], px[1], px[2], px[3]]);
+                }
+            }
+        }
+
+        let duration_ms = match frame.delay {
+            0 => DEFAULT_FRAME_DURATION_MS,
+            delay => delay as u32 * 10,
+        };
+        frames.push(Frame {
+            sprite: canvas.clone(),
+            duration_ms,
+        });
+
+        match frame.dispose {
+            ::gif::DisposalMethod::Background => {
+                for y in top..(top + fh).min(height) {
+                    for x in left..(left + fw).min(width) {
+                        canvas.set_pixel(x, y, [0, 0, 0, 0]);
+                    }
+                }
+            }
+            ::gif::DisposalMethod::Previous => {
+                if let Some(previous) = previous {
+                    canvas = previous;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if frames.is_empty() {
+        return Err(Error::corrupt("GIF", "no frames"));
+    }
+    let mut animation = Animation::from_frames(frames);
+    animation.loop_count = match decoder.repeat() {
+        ::gif::Repeat::Infinite => 0,
+        ::gif::Repeat::Finite(n) => n.saturating_add(1),
+    };
+    Ok(animation)
+}
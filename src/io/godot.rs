@@ -0,0 +1,75 @@
+//! Godot 4 `SpriteFrames` resource export.
+//!
+//! Frames are laid out on a sprite sheet and each one becomes an
+//! `AtlasTexture` sub-resource. Every tag becomes a named animation, so the
+//! `.tres` can be dropped onto an `AnimatedSprite2D` as is.
+
+use std::fmt::Write;
+
+use crate::animation::Animation;
+use crate::io::sheet::Placement;
+
+/// Write the resource for frames placed on the sheet saved as `image_name`,
+/// which Godot resolves relative to the `.tres` file.
+pub fn sprite_frames(animation: &Animation, placements: &[Placement], image_name: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"[gd_resource type="SpriteFrames" load_steps={} format=3]"#,
+        placements.len() + 2
+    );
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        r#"[ext_resource type="Texture2D" path="{}" id="1_sheet"]"#,
+        escape(image_name)
+    );
+
+    for (i, place) in placements.iter().enumerate() {
+        let s = place.source;
+        let _ = writeln!(out);
+        let _ = writeln!(out, r#"[sub_resource type="AtlasTexture" id="AtlasTexture_{i}"]"#);
+        let _ = writeln!(out, r#"atlas = ExtResource("1_sheet")"#);
+        let _ = writeln!(out, "region = Rect2({}, {}, {}, {})", place.x, place.y, s.w, s.h);
+        // Trimmed frames get their transparent border back through the margin
+        let (extra_w, extra_h) = (animation.width() - s.w, animation.height() - s.h);
+        if extra_w > 0 || extra_h > 0 {
+            let _ = writeln!(out, "margin = Rect2({}, {}, {extra_w}, {extra_h})", s.x, s.y);
+        }
+        let _ = writeln!(out, "filter_clip = true");
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[resource]");
+    let animations: Vec<String> = animation
+        .tags_or_default()
+        .iter()
+        .map(|tag| {
+            let frames = &animation.frames[tag.from..=tag.to];
+            // Godot times frames in multiples of 1 / speed, so use the
+            // shortest frame as the unit
+            let base_ms = frames.iter().map(|f| f.duration_ms).min().unwrap_or(100).max(1);
+            let entries: Vec<String> = (tag.from..=tag.to)
+                .map(|i| {
+                    format!(
+                        "{{\n\"duration\": {:?},\n\"texture\": SubResource(\"AtlasTexture_{i}\")\n}}",
+                        animation.frames[i].duration_ms as f32 / base_ms as f32
+                    )
+                })
+                .collect();
+            format!(
+                "{{\n\"frames\": [{}],\n\"loop\": {},\n\"name\": &\"{}\",\n\"speed\": {:?}\n}}",
+                entries.join(", "),
+                tag.looping,
+                escape(&tag.name),
+                1000.0 / base_ms as f32
+            )
+        })
+        .collect();
+    let _ = writeln!(out, "animations = [{}]", animations.join(", "));
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,196 @@
+//! PICO-8 `.p8` text cartridges: the `__gfx__` sprite sheet and `__gff__`
+//! sprite flags. Everything else in the cart is kept verbatim.
+
+use crate::io::Error;
+use crate::sprite::Sprite;
+
+pub const SHEET_SIZE: u32 = 128;
+pub const TILE_SIZE: u32 = 8;
+pub const SPRITE_COUNT: usize = 256;
+
+/// The fixed PICO-8 palette, indexed by the hex digits in `__gfx__`.
+pub const PALETTE: [[u8; 4]; 16] = [
+    [0, 0, 0, 255],
+    [29, 43, 83, 255],
+    [126, 37, 83, 255],
+    [0, 135, 81, 255],
+    [171, 82, 54, 255],
+    [95, 87, 79, 255],
+    [194, 195, 199, 255],
+    [255, 241, 232, 255],
+    [255, 0, 77, 255],
+    [255, 163, 0, 255],
+    [255, 236, 39, 255],
+    [0, 228, 54, 255],
+    [41, 173, 255, 255],
+    [131, 118, 156, 255],
+    [255, 119, 168, 255],
+    [255, 204, 170, 255],
+];
+
+const HEADER: &str = "pico-8 cartridge";
+
+/// A minimal cart for sprites that didn't come from one.
+const EMPTY_CART: &str = "pico-8 cartridge // http://www.pico-8.com\nversion 42\n__lua__\n\n";
+
+/// Sections in the order PICO-8 saves them, used to place `__gfx__` or
+/// `__gff__` when the cart doesn't have it yet.
+const SECTION_ORDER: [&str; 7] = [
+    "__lua__",
+    "__gfx__",
+    "__gff__",
+    "__label__",
+    "__map__",
+    "__sfx__",
+    "__music__",
+];
+
+/// A loaded cart: its full text plus the sprite flags, one byte per sprite.
+#[derive(Clone, PartialEq)]
+pub struct Cart {
+    pub source: String,
+    pub flags: [u8; SPRITE_COUNT],
+}
+
+impl Default for Cart {
+    fn default() -> Self {
+        Self {
+            source: EMPTY_CART.into(),
+            flags: [0; SPRITE_COUNT],
+        }
+    }
+}
+
+pub fn is_p8(data: &[u8]) -> bool {
+    data.starts_with(HEADER.as_bytes())
+}
+
+/// Read the sprite sheet and flags. Missing rows and sections read as 0.
+pub fn read(data: &[u8]) -> Result<(Cart, Sprite), Error> {
+    let source = std::str::from_utf8(data)
+        .map_err(|_| Error::corrupt("PICO-8", "not valid text"))?
+        .replace("\r\n", "\n");
+
+    let mut sprite = Sprite::new(SHEET_SIZE, SHEET_SIZE);
+    for px in sprite.pixels.chunks_exact_mut(4) {
+        px.copy_from_slice(&PALETTE[0]);
+    }
+    for (y, line) in section(&source, "__gfx__")
+        .take(SHEET_SIZE as usize)
+        .enumerate()
+    {
+        for (x, digit) in line.chars().take(SHEET_SIZE as usize).enumerate() {
+            let index = digit
+                .to_digit(16)
+                .ok_or_else(|| Error::corrupt("PICO-8", "bad digit in __gfx__"))?;
+            sprite.set_pixel(x as u32, y as u32, PALETTE[index as usize]);
+        }
+    }
+
+    let mut flags = [0u8; SPRITE_COUNT];
+    let digits: Vec<char> = section(&source, "__gff__")
+        .flat_map(|line| line.chars())
+        .collect();
+    for (flag, pair) in flags.iter_mut().zip(digits.chunks_exact(2)) {
+        let hex: String = pair.iter().collect();
+        *flag = u8::from_str_radix(&hex, 16)
+            .map_err(|_| Error::corrupt("PICO-8", "bad digit in __gff__"))?;
+    }
+
+    Ok((Cart { source, flags }, sprite))
+}
+
+/// The cart text with `__gfx__` and `__gff__` replaced by the sprite and
+/// flags. Colors snap to the nearest palette entry; transparency is color 0.
+pub fn write(cart: &Cart, sprite: &Sprite) -> Result<String, Error> {
+    if sprite.width != SHEET_SIZE || sprite.height != SHEET_SIZE {
+        return Err(Error::Encode("PICO-8 sprite sheets must be 128x128".into()));
+    }
+
+    let mut gfx = String::new();
+    for y in 0..SHEET_SIZE {
+        for x in 0..SHEET_SIZE {
+            let index = nearest_index(sprite.get_pixel(x, y));
+            gfx.push(char::from_digit(index as u32, 16).unwrap());
+        }
+        gfx.push('\n');
+    }
+
+    let mut gff = String::new();
+    for row in cart.flags.chunks(128) {
+        for flag in row {
+            gff.push_str(&format!("{flag:02x}"));
+        }
+        gff.push('\n');
+    }
+
+    let source = replace_section(&cart.source, "__gfx__", &gfx);
+    Ok(replace_section(&source, "__gff__", &gff))
+}
+
+pub fn nearest_index(color: [u8; 4]) -> usize {
+    if color[3] == 0 {
+        return 0;
+    }
+    let distance =
+        |p: &[u8; 4]| -> i32 { (0..3).map(|c| (color[c] as i32 - p[c] as i32).pow(2)).sum() };
+    (0..PALETTE.len())
+        .min_by_key(|&i| distance(&PALETTE[i]))
+        .unwrap_or(0)
+}
+
+fn is_header(line: &str) -> bool {
+    line.len() > 4 && line.starts_with("__") && line.ends_with("__")
+}
+
+/// Lines of the named section, up to the next section header.
+fn section<'a>(source: &'a str, name: &'a str) -> impl Iterator<Item = &'a str> {
+    source
+        .lines()
+        .skip_while(move |line| line.trim_end() != name)
+        .skip(1)
+        .take_while(|line| !is_header(line.trim_end()))
+}
+
+/// Swap a section's body for `body`, adding the section in PICO-8's usual
+/// order if the cart doesn't have it.
+fn replace_section(source: &str, name: &str, body: &str) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut out = String::new();
+
+    if let Some(start) = lines.iter().position(|line| line.trim_end() == name) {
+        let end = lines[start + 1..]
+            .iter()
+            .position(|line| is_header(line.trim_end()))
+            .map_or(lines.len(), |i| start + 1 + i);
+        for line in &lines[..=start] {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push_str(body);
+        for line in &lines[end..] {
+            out.push_str(line);
+            out.push('\n');
+        }
+        return out;
+    }
+
+    // Insert before the first section that PICO-8 puts after this one
+    let later = &SECTION_ORDER[SECTION_ORDER.iter().position(|s| *s == name).unwrap_or(0) + 1..];
+    let insert_at = lines
+        .iter()
+        .position(|line| later.contains(&line.trim_end()))
+        .unwrap_or(lines.len());
+    for line in &lines[..insert_at] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str(name);
+    out.push('\n');
+    out.push_str(body);
+    for line in &lines[insert_at..] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
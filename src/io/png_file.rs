@@ -0,0 +1,155 @@
+//! PNG saving that keeps what the file was loaded with: the palette order
+//! of indexed images, `tEXt` chunks, and Spritedit's own `spEd` chunk with
+//! editor state.
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::ExportSettings;
+use crate::io::Error;
+use crate::sprite::Sprite;
+
+/// Private, ancillary, safe-to-copy chunk holding `Metadata` as JSON.
+const CHUNK_TYPE: [u8; 4] = *b"spEd";
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Editor state saved alongside the pixels.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    /// Pixels per grid box
+    pub grid: u32,
+    /// Which frame of how many this image is
+    pub frame: usize,
+    pub frames: usize,
+    pub duration_ms: u32,
+    /// Scaled export settings, missing from files saved before they were
+    #[serde(default)]
+    pub export: ExportSettings,
+}
+
+/// Chunks read back from a PNG.
+#[derive(Default)]
+pub struct PngMeta {
+    /// `tEXt` keyword/text pairs, in file order
+    pub text: Vec<(String, String)>,
+    pub metadata: Option<Metadata>,
+}
+
+/// Read `tEXt` and `spEd` chunks. Other formats give nothing.
+pub fn read_meta(data: &[u8]) -> PngMeta {
+    let mut meta = PngMeta::default();
+    for (kind, body) in chunks(data) {
+        match &kind {
+            b"tEXt" => {
+                if let Some(nul) = body.iter().position(|&b| b == 0) {
+                    meta.text
+                        .push((latin1(&body[..nul]), latin1(&body[nul + 1..])));
+                }
+            }
+            &CHUNK_TYPE => meta.metadata = serde_json::from_slice(body).ok(),
+            _ => {}
+        }
+    }
+    meta
+}
+
+/// Encode the sprite, as an indexed PNG if it fits in 256 colors. Indices
+/// follow `palette` (e.g. the one the file was loaded with), with colors it
+/// lacks appended. Returns the data and the palette size if indexed.
+pub fn encode(
+    sprite: &Sprite,
+    palette: &[[u8; 4]],
+    text: &[(String, String)],
+    metadata: &Metadata,
+) -> Result<(Vec<u8>, Option<usize>), Error> {
+    let err = |e: png::EncodingError| Error::Encode(e.to_string());
+    let indexed = indexed(sprite, palette);
+    let mut buf = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut buf, sprite.width, sprite.height);
+        encoder.set_depth(png::BitDepth::Eight);
+        match &indexed {
+            Some((colors, _)) => {
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_palette(
+                    colors
+                        .iter()
+                        .flat_map(|c| [c[0], c[1], c[2]])
+                        .collect::<Vec<_>>(),
+                );
+                // tRNS can stop after the last entry that isn't opaque
+                let alpha: Vec<u8> = colors.iter().map(|c| c[3]).collect();
+                if let Some(last) = alpha.iter().rposition(|&a| a < 255) {
+                    encoder.set_trns(alpha[..=last].to_vec());
+                }
+            }
+            None => encoder.set_color(png::ColorType::Rgba),
+        }
+        for (keyword, value) in text {
+            encoder
+                .add_text_chunk(keyword.clone(), value.clone())
+                .map_err(err)?;
+        }
+        let mut writer = encoder.write_header().map_err(err)?;
+        let json = serde_json::to_vec(metadata).map_err(|e| Error::Encode(e.to_string()))?;
+        writer
+            .write_chunk(png::chunk::ChunkType(CHUNK_TYPE), &json)
+            .map_err(err)?;
+        match &indexed {
+            Some((_, indices)) => writer.write_image_data(indices),
+            None => writer.write_image_data(&sprite.pixels),
+        }
+        .map_err(err)?;
+        writer.finish().map_err(err)?;
+    }
+    Ok((buf, indexed.map(|(colors, _)| colors.len())))
+}
+
+/// Palette and per-pixel indices, or `None` past 256 colors. Fully
+/// transparent pixels share one entry whatever their color.
+fn indexed(sprite: &Sprite, palette: &[[u8; 4]]) -> Option<(Vec<[u8; 4]>, Vec<u8>)> {
+    let mut colors: Vec<[u8; 4]> = palette.iter().take(256).copied().collect();
+    let mut indices = Vec::with_capacity(sprite.pixels.len() / 4);
+    for px in sprite.pixels.chunks_exact(4) {
+        let color = [px[0], px[1], px[2], px[3]];
+        let found = if color[3] == 0 {
+            colors.iter().position(|c| c[3] == 0)
+        } else {
+            colors.iter().position(|&c| c == color)
+        };
+        let index = match found {
+            Some(i) => i,
+            None if colors.len() < 256 => {
+                colors.push(color);
+                colors.len() - 1
+            }
+            None => return None,
+        };
+        indices.push(index as u8);
+    }
+    Some((colors, indices))
+}
+
+/// Chunk types and bodies, stopping at the first truncated chunk.
+fn chunks(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut out = Vec::new();
+    if !data.starts_with(&SIGNATURE) {
+        return out;
+    }
+    let mut pos = SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind: [u8; 4] = data[pos + 4..pos + 8].try_into().unwrap();
+        let start = pos + 8;
+        // Body plus 4 bytes of CRC
+        let Some(end) = start.checked_add(len).filter(|&e| e + 4 <= data.len()) else {
+            break;
+        };
+        out.push((kind, &data[start..end]));
+        pos = end + 4;
+    }
+    out
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
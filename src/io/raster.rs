@@ -0,0 +1,164 @@
+//! Still image formats besides PNG: BMP, TGA, QOI, and Windows icons and
+//! cursors.
+//!
+//! Icons and cursors hold several square sizes, each scaled from the sprite
+//! with nearest neighbor so pixel art stays crisp.
+
+use image::ImageEncoder;
+
+use crate::io::Error;
+use crate::sprite::Sprite;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Bmp,
+    Tga,
+    Qoi,
+    Ico,
+    Cur,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 5] = [
+        ImageFormat::Bmp,
+        ImageFormat::Tga,
+        ImageFormat::Qoi,
+        ImageFormat::Ico,
+        ImageFormat::Cur,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageFormat::Bmp => "BMP",
+            ImageFormat::Tga => "TGA",
+            ImageFormat::Qoi => "QOI",
+            ImageFormat::Ico => "ICO",
+            ImageFormat::Cur => "CUR",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Tga => "tga",
+            ImageFormat::Qoi => "qoi",
+            ImageFormat::Ico => "ico",
+            ImageFormat::Cur => "cur",
+        }
+    }
+
+    pub fn is_icon(&self) -> bool {
+        matches!(self, ImageFormat::Ico | ImageFormat::Cur)
+    }
+}
+
+/// Square sizes an icon or cursor can include.
+pub const ICON_SIZES: [u32; 7] = [16, 24, 32, 48, 64, 128, 256];
+
+#[derive(Clone)]
+pub struct ImageOptions {
+    pub format: ImageFormat,
+    /// Which of `ICON_SIZES` go into an icon or cursor
+    pub icon_sizes: [bool; ICON_SIZES.len()],
+    /// Cursor click point, in sprite pixels
+    pub hotspot: (u32, u32),
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            format: ImageFormat::Bmp,
+            icon_sizes: [true, false, true, true, false, false, false],
+            hotspot: (0, 0),
+        }
+    }
+}
+
+/// Whether `data` is a Windows cursor, which uses the icon layout with a
+/// different type field.
+pub fn is_cursor(data: &[u8]) -> bool {
+    data.starts_with(&[0, 0, 2, 0])
+}
+
+pub fn encode(sprite: &Sprite, options: &ImageOptions) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    let (w, h, pixels) = (sprite.width, sprite.height, &sprite.pixels);
+    let rgba = image::ExtendedColorType::Rgba8;
+    let result = match options.format {
+        ImageFormat::Bmp => {
+            image::codecs::bmp::BmpEncoder::new(&mut buf).write_image(pixels, w, h, rgba)
+        }
+        ImageFormat::Tga => {
+            image::codecs::tga::TgaEncoder::new(&mut buf).write_image(pixels, w, h, rgba)
+        }
+        ImageFormat::Qoi => {
+            image::codecs::qoi::QoiEncoder::new(&mut buf).write_image(pixels, w, h, rgba)
+        }
+        ImageFormat::Ico | ImageFormat::Cur => return icon(sprite, options),
+    };
+    result.map_err(|e| Error::Encode(format!("{}: {e}", options.format.name())))?;
+    Ok(buf)
+}
+
+/// ICO or CUR file with one PNG-compressed image per selected size.
+fn icon(sprite: &Sprite, options: &ImageOptions) -> Result<Vec<u8>, Error> {
+    let sizes: Vec<u32> = ICON_SIZES
+        .iter()
+        .zip(options.icon_sizes)
+        .filter(|(_, on)| *on)
+        .map(|(&size, _)| size)
+        .collect();
+    if sizes.is_empty() {
+        return Err(Error::Encode("pick at least one icon size".into()));
+    }
+    let cursor = options.format == ImageFormat::Cur;
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(&(if cursor { 2u16 } else { 1 }).to_le_bytes());
+    header.extend_from_slice(&(sizes.len() as u16).to_le_bytes());
+
+    let mut images = Vec::new();
+    let mut offset = 6 + 16 * sizes.len() as u32;
+    for &size in &sizes {
+        let (scaled, (ox, oy), (w, h)) = fit_square(sprite, size);
+        let png = super::sprite_to_png(&scaled)?;
+        // Icons store color planes and bit depth here; cursors the hotspot
+        let (field_a, field_b) = if cursor {
+            let hx = ox + options.hotspot.0.min(sprite.width - 1) * w / sprite.width;
+            let hy = oy + options.hotspot.1.min(sprite.height - 1) * h / sprite.height;
+            (hx as u16, hy as u16)
+        } else {
+            (1, 32)
+        };
+        // A size byte of 0 means 256
+        header.push(size as u8);
+        header.push(size as u8);
+        header.extend_from_slice(&[0, 0]);
+        header.extend_from_slice(&field_a.to_le_bytes());
+        header.extend_from_slice(&field_b.to_le_bytes());
+        header.extend_from_slice(&(png.len() as u32).to_le_bytes());
+        header.extend_from_slice(&offset.to_le_bytes());
+        offset += png.len() as u32;
+        images.push(png);
+    }
+    header.extend(images.concat());
+    Ok(header)
+}
+
+/// Scale the sprite into a `size` square, keeping its aspect ratio and
+/// centering it. Returns the image, where the sprite landed and its size.
+fn fit_square(sprite: &Sprite, size: u32) -> (Sprite, (u32, u32), (u32, u32)) {
+    let longest = sprite.width.max(sprite.height);
+    let w = (sprite.width * size / longest).max(1);
+    let h = (sprite.height * size / longest).max(1);
+    let (ox, oy) = ((size - w) / 2, (size - h) / 2);
+    let mut out = Sprite::new(size, size);
+    for y in 0..h {
+        for x in 0..w {
+            let px = sprite.get_pixel(x * sprite.width / w, y * sprite.height / h);
+            out.set_pixel(ox + x, oy + y, px);
+        }
+    }
+    (out, (ox, oy), (w, h))
+}
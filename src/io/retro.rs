@@ -0,0 +1,253 @@
+//! Planar 8x8 tile formats used by retro consoles: NES CHR, Game Boy 2bpp
+//! and SNES 4bpp.
+//!
+//! Pixels map to palette indices through a user-picked palette. Colors not
+//! in the palette snap to the nearest entry, and transparent pixels always
+//! use index 0.
+
+use crate::io::Error;
+use crate::sprite::Sprite;
+
+pub const TILE_SIZE: u32 = 8;
+
+/// Tiles per row when laying out an imported tile file, as tile viewers do.
+const IMPORT_COLUMNS: u32 = 16;
+
+/// Starting palette: transparent, three grays for 2bpp formats, then a
+/// gray ramp filling out the 16 colors of 4bpp.
+pub const DEFAULT_PALETTE: [[u8; 4]; 16] = [
+    [0, 0, 0, 0],
+    [85, 85, 85, 255],
+    [170, 170, 170, 255],
+    [255, 255, 255, 255],
+    [0, 0, 0, 255],
+    [23, 23, 23, 255],
+    [46, 46, 46, 255],
+    [69, 69, 69, 255],
+    [93, 93, 93, 255],
+    [116, 116, 116, 255],
+    [139, 139, 139, 255],
+    [162, 162, 162, 255],
+    [185, 185, 185, 255],
+    [209, 209, 209, 255],
+    [232, 232, 232, 255],
+    [245, 245, 245, 255],
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TileFormat {
+    /// Each tile is 8 bytes of bit plane 0 followed by 8 bytes of plane 1
+    NesChr,
+    /// Each row is a plane 0 byte followed by its plane 1 byte
+    GameBoy2bpp,
+    /// Planes 0/1 interleaved per row, then planes 2/3 the same way
+    Snes4bpp,
+}
+
+impl TileFormat {
+    pub const ALL: [TileFormat; 3] = [
+        TileFormat::NesChr,
+        TileFormat::GameBoy2bpp,
+        TileFormat::Snes4bpp,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileFormat::NesChr => "NES CHR",
+            TileFormat::GameBoy2bpp => "Game Boy 2bpp",
+            TileFormat::Snes4bpp => "SNES 4bpp",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TileFormat::NesChr => "chr",
+            TileFormat::GameBoy2bpp => "2bpp",
+            TileFormat::Snes4bpp => "4bpp",
+        }
+    }
+
+    /// Number of palette entries a tile can use.
+    pub fn colors(&self) -> usize {
+        match self {
+            TileFormat::NesChr | TileFormat::GameBoy2bpp => 4,
+            TileFormat::Snes4bpp => 16,
+        }
+    }
+
+    pub fn tile_bytes(&self) -> usize {
+        match self {
+            TileFormat::NesChr | TileFormat::GameBoy2bpp => 16,
+            TileFormat::Snes4bpp => 32,
+        }
+    }
+}
+
+pub struct TileExport {
+    pub data: Vec<u8>,
+    /// Tile index for every grid cell, row by row, when deduplicating.
+    /// One byte per entry, or two little-endian bytes for SNES.
+    pub map: Option<Vec<u8>>,
+    pub tile_count: usize,
+}
+
+/// Encode every whole 8x8 cell of the sprite. With `dedup`, repeated tiles
+/// are written once and a tile map records which tile each cell uses.
+pub fn export(
+    sprite: &Sprite,
+    format: TileFormat,
+    palette: &[[u8; 4]],
+    dedup: bool,
+) -> Result<TileExport, Error> {
+    let (columns, rows) = (sprite.width / TILE_SIZE, sprite.height / TILE_SIZE);
+    if columns == 0 || rows == 0 {
+        return Err(Error::Encode("sprite is smaller than one 8x8 tile".into()));
+    }
+    let palette = &palette[..format.colors()];
+
+    let mut tiles: Vec<Vec<u8>> = Vec::new();
+    let mut map = Vec::new();
+    for row in 0..rows {
+        for col in 0..columns {
+            let mut indices = [0u8; 64];
+            for y in 0..TILE_SIZE {
+                for x in 0..TILE_SIZE {
+                    let color = sprite.get_pixel(col * TILE_SIZE + x, row * TILE_SIZE + y);
+                    indices[(y * TILE_SIZE + x) as usize] = nearest_index(color, palette);
+                }
+            }
+            let tile = encode_tile(&indices, format);
+            let index = match tiles.iter().position(|t| dedup && *t == tile) {
+                Some(existing) => existing,
+                None => {
+                    tiles.push(tile);
+                    tiles.len() - 1
+                }
+            };
+            map.push(index);
+        }
+    }
+
+    let map = if dedup {
+        let bytes = match format {
+            TileFormat::Snes4bpp => map.iter().flat_map(|&i| (i as u16).to_le_bytes()).collect(),
+            _ => {
+                if tiles.len() > 256 {
+                    return Err(Error::Encode(format!(
+                        "{} unique tiles don't fit an 8-bit tile map",
+                        tiles.len()
+                    )));
+                }
+                map.iter().map(|&i| i as u8).collect()
+            }
+        };
+        Some(bytes)
+    } else {
+        None
+    };
+    Ok(TileExport {
+        tile_count: tiles.len(),
+        data: tiles.concat(),
+        map,
+    })
+}
+
+/// Decode a tile file into a sprite 16 tiles wide.
+pub fn import(data: &[u8], format: TileFormat, palette: &[[u8; 4]]) -> Result<Sprite, Error> {
+    let tile_count = data.len() / format.tile_bytes();
+    if tile_count == 0 {
+        return Err(Error::corrupt(format.name(), "too small for one tile"));
+    }
+    let columns = (tile_count as u32).min(IMPORT_COLUMNS);
+    let rows = (tile_count as u32).div_ceil(IMPORT_COLUMNS);
+    Error::check_dimensions(columns * TILE_SIZE, rows * TILE_SIZE)?;
+    let mut sprite = Sprite::new(columns * TILE_SIZE, rows * TILE_SIZE);
+
+    for (i, tile) in data.chunks_exact(format.tile_bytes()).enumerate() {
+        let indices = decode_tile(tile, format);
+        let (col, row) = (i as u32 % IMPORT_COLUMNS, i as u32 / IMPORT_COLUMNS);
+        for y in 0..TILE_SIZE {
+            for x in 0..TILE_SIZE {
+                let index = indices[(y * TILE_SIZE + x) as usize] as usize;
+                sprite.set_pixel(col * TILE_SIZE + x, row * TILE_SIZE + y, palette[index]);
+            }
+        }
+    }
+    Ok(sprite)
+}
+
+/// Byte offset of bit plane `plane` for tile row `y`.
+fn plane_offset(format: TileFormat, plane: usize, y: usize) -> usize {
+    match format {
+        TileFormat::NesChr => plane * 8 + y,
+        TileFormat::GameBoy2bpp => y * 2 + plane,
+        TileFormat::Snes4bpp => (plane / 2) * 16 + y * 2 + plane % 2,
+    }
+}
+
+fn bit_planes(format: TileFormat) -> usize {
+    format.colors().trailing_zeros() as usize
+}
+
+fn encode_tile(indices: &[u8; 64], format: TileFormat) -> Vec<u8> {
+    let mut out = vec![0u8; format.tile_bytes()];
+    for y in 0..8 {
+        for x in 0..8 {
+            let index = indices[y * 8 + x];
+            for plane in 0..bit_planes(format) {
+                if index >> plane & 1 == 1 {
+                    // Leftmost pixel is the most significant bit
+                    out[plane_offset(format, plane, y)] |= 0x80 >> x;
+                }
+            }
+        }
+    }
+    out
+}
+
+fn decode_tile(tile: &[u8], format: TileFormat) -> [u8; 64] {
+    let mut indices = [0u8; 64];
+    for y in 0..8 {
+        for x in 0..8 {
+            for plane in 0..bit_planes(format) {
+                if tile[plane_offset(format, plane, y)] & (0x80 >> x) != 0 {
+                    indices[y * 8 + x] |= 1 << plane;
+                }
+            }
+        }
+    }
+    indices
+}
+
+fn nearest_index(color: [u8; 4], palette: &[[u8; 4]]) -> u8 {
+    if color[3] == 0 {
+        return 0;
+    }
+    let distance = |p: &[u8; 4]| -> u32 {
+        (0..4)
+            .map(|c| (color[c] as i32 - p[c] as i32).pow(2) as u32)
+            .sum()
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, p)| distance(p))
+        .map_or(0, |(i, _)| i as u8)
+}
+
+/// The first colors used in the sprite, transparent first, padded with
+/// black to `count` entries.
+pub fn palette_from_sprite(sprite: &Sprite, count: usize) -> Vec<[u8; 4]> {
+    let mut colors = vec![[0, 0, 0, 0]];
+    for px in sprite.pixels.chunks_exact(4) {
+        if colors.len() == count {
+            break;
+        }
+        let color = [px[0], px[1], px[2], px[3]];
+        if color[3] > 0 && !colors.contains(&color) {
+            colors.push(color);
+        }
+    }
+    colors.resize(count, [0, 0, 0, 255]);
+    colors
+}
@@ -0,0 +1,543 @@
+//! Sprite sheet (texture atlas) export with a TexturePacker-style JSON
+//! descriptor, readable by most engines and by Aseprite's own importers,
+//! plus slicing third-party sheets back into frames.
+
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+use crate::animation::{Animation, Frame, DEFAULT_FRAME_DURATION_MS};
+use crate::sprite::Sprite;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SheetLayout {
+    /// Fixed number of columns, every cell the full frame size
+    Grid,
+    /// All frames side by side in a single row
+    Row,
+    /// Frames trimmed to their visible pixels and packed onto shelves
+    Packed,
+}
+
+impl SheetLayout {
+    pub const ALL: [SheetLayout; 3] = [SheetLayout::Grid, SheetLayout::Row, SheetLayout::Packed];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SheetLayout::Grid => "Grid",
+            SheetLayout::Row => "Row",
+            SheetLayout::Packed => "Packed",
+        }
+    }
+}
+
+/// The two frame list shapes TexturePacker and Aseprite write: frames keyed
+/// by name, or an array of frames carrying a `filename` field.
+#[derive(Clone, Copy, PartialEq)]
+pub enum JsonFormat {
+    Hash,
+    Array,
+}
+
+#[derive(Clone)]
+pub struct SheetOptions {
+    pub layout: SheetLayout,
+    /// Columns in the grid layout
+    pub columns: u32,
+    /// Empty pixels between neighbouring frames
+    pub padding: u32,
+    /// How far each frame's edge pixels are repeated outward, to stop
+    /// filtering from bleeding neighbours into it
+    pub extrude: u32,
+    pub power_of_two: bool,
+    pub json_format: JsonFormat,
+    /// Pivot as a fraction of the frame size
+    pub pivot: [f32; 2],
+}
+
+impl Default for SheetOptions {
+    fn default() -> Self {
+        Self {
+            layout: SheetLayout::Grid,
+            columns: 4,
+            padding: 0,
+            extrude: 0,
+            power_of_two: false,
+            json_format: JsonFormat::Hash,
+            pivot: [0.5, 0.5],
+        }
+    }
+}
+
+/// Where one frame ends up on the sheet.
+pub struct Placement {
+    /// Visible part of the frame, in frame coordinates
+    pub source: Rect,
+    /// Top-left of the frame (inside its extrusion) on the sheet
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+pub struct Sheet {
+    pub image: Sprite,
+    pub json: String,
+}
+
+/// Size of the sheet `build` would produce, for previewing in the dialog.
+pub fn sheet_size(animation: &Animation, options: &SheetOptions) -> (u32, u32) {
+    layout(animation, options).1
+}
+
+/// Lay the frames out and render the sheet image, returning it with each
+/// frame's placement for exporters that write their own descriptor.
+pub fn render(animation: &Animation, options: &SheetOptions) -> (Sprite, Vec<Placement>) {
+    let (placements, (width, height)) = layout(animation, options);
+    let mut image = Sprite::new(width, height);
+    for (frame, place) in animation.frames.iter().zip(&placements) {
+        blit_extruded(&mut image, &frame.sprite, place, options.extrude);
+    }
+    (image, placements)
+}
+
+/// Lay the frames out and render the sheet image plus its JSON descriptor.
+/// `image_name` is recorded in the descriptor's `meta.image`.
+pub fn build(animation: &Animation, options: &SheetOptions, image_name: &str) -> Sheet {
+    let (image, placements) = render(animation, options);
+    let (width, height) = (image.width, image.height);
+
+    let stem = image_name
+        .rsplit_once('.')
+        .map_or(image_name, |(stem, _)| stem);
+    let entries: Vec<(String, FrameEntry)> = animation
+        .frames
+        .iter()
+        .zip(&placements)
+        .enumerate()
+        .map(|(i, (frame, place))| {
+            let s = place.source;
+            let trimmed = s.w != animation.width() || s.h != animation.height();
+            let entry = FrameEntry {
+                filename: None,
+                frame: JsonRect::new(place.x, place.y, s.w, s.h),
+                rotated: false,
+                trimmed,
+                sprite_source_size: JsonRect::new(s.x, s.y, s.w, s.h),
+                source_size: JsonSize {
+                    w: animation.width(),
+                    h: animation.height(),
+                },
+                duration: frame.duration_ms,
+                pivot: JsonPoint {
+                    x: options.pivot[0],
+                    y: options.pivot[1],
+                },
+            };
+            (format!("{stem} {i}.png"), entry)
+        })
+        .collect();
+
+    let frames = match options.json_format {
+        JsonFormat::Hash => JsonFrames::Hash(entries),
+        JsonFormat::Array => JsonFrames::Array(
+            entries
+                .into_iter()
+                .map(|(name, entry)| FrameEntry {
+                    filename: Some(name),
+                    ..entry
+                })
+                .collect(),
+        ),
+    };
+    let descriptor = Descriptor {
+        frames,
+        meta: Meta {
+            app: "spritedit",
+            version: env!("CARGO_PKG_VERSION"),
+            image: image_name.to_string(),
+            format: "RGBA8888",
+            size: JsonSize {
+                w: width,
+                h: height,
+            },
+            scale: "1",
+            frame_tags: animation
+                .tags
+                .iter()
+                .map(|tag| JsonTag {
+                    name: tag.name.clone(),
+                    from: tag.from,
+                    to: tag.to,
+                    direction: "forward",
+                })
+                .collect(),
+        },
+    };
+    let json = serde_json::to_string_pretty(&descriptor).expect("Failed to serialize atlas");
+    Sheet { image, json }
+}
+
+fn layout(animation: &Animation, options: &SheetOptions) -> (Vec<Placement>, (u32, u32)) {
+    let sources: Vec<Rect> = animation
+        .frames
+        .iter()
+        .map(|frame| match options.layout {
+            SheetLayout::Packed => visible_bounds(&frame.sprite),
+            _ => Rect {
+                x: 0,
+                y: 0,
+                w: frame.sprite.width,
+                h: frame.sprite.height,
+            },
+        })
+        .collect();
+
+    let e = options.extrude;
+    let pad = options.padding;
+    // Cells include the extrusion on every side
+    let cell = |r: &Rect| (r.w + 2 * e, r.h + 2 * e);
+
+    let mut placements = Vec::with_capacity(sources.len());
+    let (mut width, mut height) = (0, 0);
+    match options.layout {
+        SheetLayout::Grid | SheetLayout::Row => {
+            let columns = match options.layout {
+                SheetLayout::Grid => options.columns.clamp(1, sources.len() as u32),
+                _ => sources.len() as u32,
+            };
+            for (i, source) in sources.iter().enumerate() {
+                let (cw, ch) = cell(source);
+                let (col, row) = (i as u32 % columns, i as u32 / columns);
+                let (x, y) = (col * (cw + pad), row * (ch + pad));
+                width = width.max(x + cw);
+                height = height.max(y + ch);
+                placements.push(Placement {
+                    source: *source,
+                    x: x + e,
+                    y: y + e,
+                });
+            }
+        }
+        SheetLayout::Packed => {
+            // Shelf packing: tallest frames first, filling rows left to right
+            // up to a width that keeps the sheet roughly square.
+            let area: u32 = sources
+                .iter()
+                .map(|s| {
+                    let (w, h) = cell(s);
+                    (w + pad) * (h + pad)
+                })
+                .sum();
+            let widest = sources.iter().map(|s| cell(s).0).max().unwrap_or(1);
+            let mut max_width = ((area as f32).sqrt().ceil() as u32).max(widest);
+            if options.power_of_two {
+                max_width = max_width.next_power_of_two();
+            }
+
+            let mut order: Vec<usize> = (0..sources.len()).collect();
+            order.sort_by_key(|&i| std::cmp::Reverse(sources[i].h));
+
+            let mut slots = vec![(0, 0); sources.len()];
+            let (mut x, mut y, mut shelf_h) = (0, 0, 0);
+            for i in order {
+                let (cw, ch) = cell(&sources[i]);
+                if x > 0 && x + cw > max_width {
+                    x = 0;
+                    y += shelf_h + pad;
+                    shelf_h = 0;
+                }
+                slots[i] = (x, y);
+                width = width.max(x + cw);
+                height = height.max(y + ch);
+                x += cw + pad;
+                shelf_h = shelf_h.max(ch);
+            }
+            for (source, (x, y)) in sources.iter().zip(slots) {
+                placements.push(Placement {
+                    source: *source,
+                    x: x + e,
+                    y: y + e,
+                });
+            }
+        }
+    }
+
+    if options.power_of_two {
+        width = width.next_power_of_two();
+        height = height.next_power_of_two();
+    }
+    (placements, (width.max(1), height.max(1)))
+}
+
+/// Bounding box of the sprite's non-transparent pixels. A fully transparent
+/// frame keeps a single pixel so it still gets a rect in the atlas.
+fn visible_bounds(sprite: &Sprite) -> Rect {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    for y in 0..sprite.height {
+        for x in 0..sprite.width {
+            if sprite.get_pixel(x, y)[3] > 0 {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+    if min_x > max_x {
+        return Rect {
+            x: 0,
+            y: 0,
+            w: 1,
+            h: 1,
+        };
+    }
+    Rect {
+        x: min_x,
+        y: min_y,
+        w: max_x - min_x + 1,
+        h: max_y - min_y + 1,
+    }
+}
+
+/// Copy the frame's visible rect onto the sheet, repeating its edge pixels
+/// `extrude` pixels outward.
+fn blit_extruded(sheet: &mut Sprite, frame: &Sprite, place: &Placement, extrude: u32) {
+    let s = place.source;
+    let e = extrude as i64;
+    for dy in -e..s.h as i64 + e {
+        for dx in -e..s.w as i64 + e {
+            let sx = s.x + dx.clamp(0, s.w as i64 - 1) as u32;
+            let sy = s.y + dy.clamp(0, s.h as i64 - 1) as u32;
+            let tx = (place.x as i64 + dx) as u32;
+            let ty = (place.y as i64 + dy) as u32;
+            sheet.set_pixel(tx, ty, frame.get_pixel(sx, sy));
+        }
+    }
+}
+
+// --- Slicing ---
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SliceMode {
+    /// Fixed cell size with an outer offset and spacing between cells
+    Grid,
+    /// One frame per connected island of visible pixels
+    Islands,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct SliceOptions {
+    pub mode: SliceMode,
+    pub cell_size: (u32, u32),
+    /// Margin before the first cell
+    pub offset: (u32, u32),
+    /// Gap between neighbouring cells
+    pub spacing: (u32, u32),
+    /// Leave out grid cells with no visible pixels
+    pub skip_empty: bool,
+}
+
+impl Default for SliceOptions {
+    fn default() -> Self {
+        Self {
+            mode: SliceMode::Grid,
+            cell_size: (16, 16),
+            offset: (0, 0),
+            spacing: (0, 0),
+            skip_empty: true,
+        }
+    }
+}
+
+/// The rectangles `slice` would cut out of `sheet`, in reading order.
+pub fn slice_rects(sheet: &Sprite, options: &SliceOptions) -> Vec<Rect> {
+    match options.mode {
+        SliceMode::Grid => grid_cells(sheet, options),
+        SliceMode::Islands => islands(sheet),
+    }
+}
+
+fn grid_cells(sheet: &Sprite, options: &SliceOptions) -> Vec<Rect> {
+    let (w, h) = (options.cell_size.0.max(1), options.cell_size.1.max(1));
+    let mut cells = Vec::new();
+    let mut y = options.offset.1;
+    while y + h <= sheet.height {
+        let mut x = options.offset.0;
+        while x + w <= sheet.width {
+            let cell = Rect { x, y, w, h };
+            if !options.skip_empty || has_visible_pixels(sheet, cell) {
+                cells.push(cell);
+            }
+            x += w + options.spacing.0;
+        }
+        y += h + options.spacing.1;
+    }
+    cells
+}
+
+fn has_visible_pixels(sheet: &Sprite, r: Rect) -> bool {
+    (r.y..r.y + r.h).any(|y| (r.x..r.x + r.w).any(|x| sheet.get_pixel(x, y)[3] > 0))
+}
+
+/// Bounding boxes of 8-connected groups of visible pixels, sorted top to
+/// bottom, then left to right.
+fn islands(sheet: &Sprite) -> Vec<Rect> {
+    let (w, h) = (sheet.width as i64, sheet.height as i64);
+    let mut seen = vec![false; (sheet.width * sheet.height) as usize];
+    let mut found = Vec::new();
+
+    for start in 0..seen.len() {
+        let (sx, sy) = (start as u32 % sheet.width, start as u32 / sheet.width);
+        if seen[start] || sheet.get_pixel(sx, sy)[3] == 0 {
+            continue;
+        }
+        seen[start] = true;
+        let (mut min, mut max) = ((sx as i64, sy as i64), (sx as i64, sy as i64));
+        let mut stack = vec![(sx as i64, sy as i64)];
+        while let Some((x, y)) = stack.pop() {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+            for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= w || ny >= h {
+                    continue;
+                }
+                let i = (ny * w + nx) as usize;
+                if !seen[i] && sheet.get_pixel(nx as u32, ny as u32)[3] > 0 {
+                    seen[i] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        found.push(Rect {
+            x: min.0 as u32,
+            y: min.1 as u32,
+            w: (max.0 - min.0 + 1) as u32,
+            h: (max.1 - min.1 + 1) as u32,
+        });
+    }
+
+    found.sort_by_key(|r| (r.y, r.x));
+    found
+}
+
+/// Cut `rects` out of the sheet as frames. Frames share one size, so
+/// islands smaller than the largest are placed bottom-centre, which keeps
+/// characters standing on the same baseline.
+pub fn slice(sheet: &Sprite, rects: &[Rect]) -> Option<Animation> {
+    let frame_w = rects.iter().map(|r| r.w).max()?;
+    let frame_h = rects.iter().map(|r| r.h).max()?;
+    let frames = rects
+        .iter()
+        .map(|r| {
+            let mut sprite = Sprite::new(frame_w, frame_h);
+            let (ox, oy) = ((frame_w - r.w) / 2, frame_h - r.h);
+            for y in 0..r.h {
+                for x in 0..r.w {
+                    sprite.set_pixel(ox + x, oy + y, sheet.get_pixel(r.x + x, r.y + y));
+                }
+            }
+            Frame {
+                sprite,
+                duration_ms: DEFAULT_FRAME_DURATION_MS,
+            }
+        })
+        .collect();
+    Some(Animation::from_frames(frames))
+}
+
+// --- JSON descriptor ---
+
+#[derive(Serialize)]
+struct Descriptor {
+    frames: JsonFrames,
+    meta: Meta,
+}
+
+enum JsonFrames {
+    Hash(Vec<(String, FrameEntry)>),
+    Array(Vec<FrameEntry>),
+}
+
+// Written by hand so hash keys keep frame order instead of being sorted.
+impl Serialize for JsonFrames {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonFrames::Hash(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (name, entry) in entries {
+                    map.serialize_entry(name, entry)?;
+                }
+                map.end()
+            }
+            JsonFrames::Array(entries) => entries.serialize(serializer),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FrameEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    frame: JsonRect,
+    rotated: bool,
+    trimmed: bool,
+    sprite_source_size: JsonRect,
+    source_size: JsonSize,
+    duration: u32,
+    pivot: JsonPoint,
+}
+
+#[derive(Serialize)]
+struct JsonRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+impl JsonRect {
+    fn new(x: u32, y: u32, w: u32, h: u32) -> Self {
+        Self { x, y, w, h }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Serialize)]
+struct JsonPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Meta {
+    app: &'static str,
+    version: &'static str,
+    image: String,
+    format: &'static str,
+    size: JsonSize,
+    scale: &'static str,
+    frame_tags: Vec<JsonTag>,
+}
+
+/// Aseprite's tag entry, which most atlas loaders read for animation names
+#[derive(Serialize)]
+struct JsonTag {
+    name: String,
+    from: usize,
+    to: usize,
+    direction: &'static str,
+}
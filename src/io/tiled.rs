@@ -0,0 +1,208 @@
+//! Tiled tileset (.tsx) and map (.tmx) export.
+//!
+//! The sprite is cut into square tiles of the grid size. Animations put each
+//! frame's tiles in their own band below the first, and tiles that change
+//! between frames get an `<animation>` pointing at their copies.
+
+use std::fmt::Write;
+
+use crate::animation::Animation;
+use crate::io::Error;
+use crate::sprite::Sprite;
+use crate::tileset::Tileset;
+
+#[derive(Clone, Default)]
+pub struct TiledOptions {
+    /// Pixels between neighbouring tiles in the exported image
+    pub spacing: u32,
+    /// Pixels around the edge of the exported image
+    pub margin: u32,
+    /// Also write a map that places every tile where it sits in the sprite
+    pub write_map: bool,
+}
+
+pub struct TiledExport {
+    pub image: Sprite,
+    pub tsx: String,
+    pub tmx: Option<String>,
+}
+
+/// Columns and rows of whole tiles in a `width` x `height` sprite.
+pub fn tile_grid(width: u32, height: u32, tile_size: u32) -> (u32, u32) {
+    (width / tile_size, height / tile_size)
+}
+
+/// Build the tileset image and XML. Files are named after `name`: the
+/// tileset refers to `name.png` and the map to `name.tsx`.
+pub fn export(
+    animation: &Animation,
+    tile_size: u32,
+    tileset: &Tileset,
+    options: &TiledOptions,
+    name: &str,
+) -> Result<TiledExport, Error> {
+    let (columns, rows) = tile_grid(animation.width(), animation.height(), tile_size);
+    if columns == 0 || rows == 0 {
+        return Err(Error::Encode(format!(
+            "sprite is smaller than one {tile_size}x{tile_size} tile"
+        )));
+    }
+    let per_frame = columns * rows;
+    let frame_count = animation.frames.len() as u32;
+    let tile_count = per_frame * frame_count;
+
+    let step = tile_size + options.spacing;
+    let image_w = 2 * options.margin + columns * step - options.spacing;
+    let image_h = 2 * options.margin + rows * frame_count * step - options.spacing;
+    let mut image = Sprite::new(image_w, image_h);
+    for (f, frame) in animation.frames.iter().enumerate() {
+        for row in 0..rows {
+            for col in 0..columns {
+                let band_row = f as u32 * rows + row;
+                let (dx, dy) = (
+                    options.margin + col * step,
+                    options.margin + band_row * step,
+                );
+                for y in 0..tile_size {
+                    for x in 0..tile_size {
+                        let px = frame
+                            .sprite
+                            .get_pixel(col * tile_size + x, row * tile_size + y);
+                        image.set_pixel(dx + x, dy + y, px);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut tsx = String::new();
+    let _ = writeln!(tsx, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        tsx,
+        r#"<tileset version="1.10" tiledversion="1.10.2" name="{}" tilewidth="{tile_size}" tileheight="{tile_size}" spacing="{}" margin="{}" tilecount="{tile_count}" columns="{columns}">"#,
+        escape(name),
+        options.spacing,
+        options.margin,
+    );
+    let _ = writeln!(
+        tsx,
+        r#" <image source="{}.png" width="{image_w}" height="{image_h}"/>"#,
+        escape(name)
+    );
+
+    for id in 0..per_frame {
+        let data = tileset.get(id).filter(|tile| !tile.is_empty());
+        let animated = frame_count > 1 && tile_changes(animation, id, columns, tile_size);
+        if data.is_none() && !animated {
+            continue;
+        }
+        let _ = writeln!(tsx, r#" <tile id="{id}">"#);
+        if let Some(data) = data {
+            if !data.properties.is_empty() {
+                let _ = writeln!(tsx, "  <properties>");
+                for (key, value) in &data.properties {
+                    let kind = property_type(value)
+                        .map(|t| format!(r#" type="{t}""#))
+                        .unwrap_or_default();
+                    let _ = writeln!(
+                        tsx,
+                        r#"   <property name="{}"{kind} value="{}"/>"#,
+                        escape(key),
+                        escape(value)
+                    );
+                }
+                let _ = writeln!(tsx, "  </properties>");
+            }
+            if !data.collision.is_empty() {
+                let _ = writeln!(tsx, r#"  <objectgroup draworder="index" id="2">"#);
+                for (i, [x, y, w, h]) in data.collision.iter().enumerate() {
+                    let _ = writeln!(
+                        tsx,
+                        r#"   <object id="{}" x="{x}" y="{y}" width="{w}" height="{h}"/>"#,
+                        i + 1
+                    );
+                }
+                let _ = writeln!(tsx, "  </objectgroup>");
+            }
+        }
+        if animated {
+            let _ = writeln!(tsx, "  <animation>");
+            for (f, frame) in animation.frames.iter().enumerate() {
+                let _ = writeln!(
+                    tsx,
+                    r#"   <frame tileid="{}" duration="{}"/>"#,
+                    f as u32 * per_frame + id,
+                    frame.duration_ms
+                );
+            }
+            let _ = writeln!(tsx, "  </animation>");
+        }
+        let _ = writeln!(tsx, " </tile>");
+    }
+    let _ = writeln!(tsx, "</tileset>");
+
+    let tmx = options.write_map.then(|| {
+        let mut tmx = String::new();
+        let _ = writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            tmx,
+            r#"<map version="1.10" tiledversion="1.10.2" orientation="orthogonal" renderorder="right-down" width="{columns}" height="{rows}" tilewidth="{tile_size}" tileheight="{tile_size}" infinite="0" nextlayerid="2" nextobjectid="1">"#
+        );
+        let _ = writeln!(
+            tmx,
+            r#" <tileset firstgid="1" source="{}.tsx"/>"#,
+            escape(name)
+        );
+        let _ = writeln!(
+            tmx,
+            r#" <layer id="1" name="Tile Layer 1" width="{columns}" height="{rows}">"#
+        );
+        let _ = writeln!(tmx, r#"  <data encoding="csv">"#);
+        for row in 0..rows {
+            let gids: Vec<String> = (0..columns)
+                .map(|col| (row * columns + col + 1).to_string())
+                .collect();
+            let sep = if row + 1 < rows { "," } else { "" };
+            let _ = writeln!(tmx, "{}{sep}", gids.join(","));
+        }
+        let _ = writeln!(tmx, "  </data>");
+        let _ = writeln!(tmx, " </layer>");
+        let _ = writeln!(tmx, "</map>");
+        tmx
+    });
+
+    Ok(TiledExport { image, tsx, tmx })
+}
+
+/// Whether tile `id` looks different in any frame than in the first.
+fn tile_changes(animation: &Animation, id: u32, columns: u32, tile_size: u32) -> bool {
+    let (x0, y0) = ((id % columns) * tile_size, (id / columns) * tile_size);
+    let first = &animation.frames[0].sprite;
+    animation.frames[1..].iter().any(|frame| {
+        (0..tile_size).any(|y| {
+            (0..tile_size)
+                .any(|x| frame.sprite.get_pixel(x0 + x, y0 + y) != first.get_pixel(x0 + x, y0 + y))
+        })
+    })
+}
+
+/// Tiled property type for a value typed in as text; plain strings need no
+/// type attribute.
+fn property_type(value: &str) -> Option<&'static str> {
+    if value == "true" || value == "false" {
+        Some("bool")
+    } else if value.parse::<i64>().is_ok() {
+        Some("int")
+    } else if value.parse::<f64>().is_ok() {
+        Some("float")
+    } else {
+        None
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
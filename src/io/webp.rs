@@ -0,0 +1,78 @@
+//! Lossless animated WebP export.
+//!
+//! Each frame is encoded as a VP8L bitstream and wrapped in the extended
+//! container's ANIM/ANMF chunks, which the encoder crate doesn't write itself.
+
+use image_webp::{ColorType, WebPEncoder};
+
+use crate::animation::Animation;
+use crate::io::Error;
+
+// VP8X feature flags
+const ANIMATION_FLAG: u8 = 1 << 1;
+const ALPHA_FLAG: u8 = 1 << 4;
+
+// ANMF flags: bit 1 set means "do not blend", so every frame replaces the
+// canvas outright and its own alpha is kept.
+const NO_BLEND: u8 = 1 << 1;
+
+/// Largest frame duration ANMF's 24-bit field can hold.
+const MAX_DURATION_MS: u32 = 0xFF_FFFF;
+
+pub fn write(animation: &Animation) -> Result<Vec<u8>, Error> {
+    let (width, height) = (animation.width(), animation.height());
+
+    let mut vp8x = vec![ANIMATION_FLAG | ALPHA_FLAG, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(width - 1));
+    vp8x.extend_from_slice(&u24(height - 1));
+
+    // Transparent background; loop count counts plays with 0 meaning forever
+    let mut anim = vec![0, 0, 0, 0];
+    anim.extend_from_slice(&animation.loop_count.to_le_bytes());
+
+    let mut body = b"WEBP".to_vec();
+    push_chunk(&mut body, b"VP8X", &vp8x);
+    push_chunk(&mut body, b"ANIM", &anim);
+
+    for frame in &animation.frames {
+        let mut anmf = Vec::new();
+        anmf.extend_from_slice(&u24(0)); // x offset / 2
+        anmf.extend_from_slice(&u24(0)); // y offset / 2
+        anmf.extend_from_slice(&u24(width - 1));
+        anmf.extend_from_slice(&u24(height - 1));
+        anmf.extend_from_slice(&u24(frame.duration_ms.min(MAX_DURATION_MS)));
+        anmf.push(NO_BLEND);
+        anmf.extend_from_slice(&encode_vp8l_chunk(&frame.sprite.pixels, width, height)?);
+        push_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut out = b"RIFF".to_vec();
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+/// Encode one RGBA frame and return its VP8L chunk, header included.
+fn encode_vp8l_chunk(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, Error> {
+    let mut file = Vec::new();
+    WebPEncoder::new(&mut file)
+        .encode(pixels, width, height, ColorType::Rgba8)
+        .map_err(|e| Error::Encode(e.to_string()))?;
+    // Without metadata the encoder writes the simple container: a 12 byte
+    // RIFF/WEBP header followed by the lone VP8L chunk.
+    Ok(file.split_off(12))
+}
+
+fn push_chunk(out: &mut Vec<u8>, name: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(name);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let [a, b, c, _] = value.to_le_bytes();
+    [a, b, c]
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sprite::Sprite;
+
+/// Per-pixel elevation used by isometric block mode. One level is one pixel
+/// of vertical extrusion in the rendered tile.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeightMap {
+    pub width: u32,
+    pub height: u32,
+    pub heights: Vec<u8>,
+}
+
+impl HeightMap {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            heights: vec![0; (width * height) as usize],
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        if x < self.width && y < self.height {
+            self.heights[(y * self.width + x) as usize]
+        } else {
+            0
+        }
+    }
+
+    pub fn raise(&mut self, x: u32, y: u32, amount: u8) {
+        if x < self.width && y < self.height {
+            let h = &mut self.heights[(y * self.width + x) as usize];
+            *h = h.saturating_add(amount);
+        }
+    }
+
+    pub fn lower(&mut self, x: u32, y: u32, amount: u8) {
+        if x < self.width && y < self.height {
+            let h = &mut self.heights[(y * self.width + x) as usize];
+            *h = h.saturating_sub(amount);
+        }
+    }
+
+    pub fn max(&self) -> u8 {
+        self.heights.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Brightness multipliers for the two visible side faces of a block.
+pub const LEFT_FACE_SHADE: f32 = 0.8;
+pub const RIGHT_FACE_SHADE: f32 = 0.6;
+
+pub fn shade(color: [u8; 4], factor: f32) -> [u8; 4] {
+    let [r, g, b, a] = color;
+    [
+        (r as f32 * factor) as u8,
+        (g as f32 * factor) as u8,
+        (b as f32 * factor) as u8,
+        a,
+    ]
+}
+
+/// Standard 2:1 tile sizes offered by "New Isometric Tile".
+pub const TILE_PRESETS: [(u32, u32); 4] = [(16, 8), (32, 16), (64, 32), (128, 64)];
+
+/// Whether pixel (x, y) lies inside the diamond footprint of a `width` x
+/// `height` isometric tile. Rows step two pixels per side, so a 32x16 tile
+/// has rows 2, 6, .., 30 pixels wide and tiles placed half a tile apart
+/// interlock without gaps or overlap.
+pub fn diamond_contains(width: u32, height: u32, x: u32, y: u32) -> bool {
+    let half_w = width as f32 / 2.0;
+    let half_h = height as f32 / 2.0;
+    let dx = (x as f32 + 0.5 - half_w).abs() / half_w;
+    let dy = (y as f32 + 0.5 - half_h).abs() / half_h;
+    dx + dy < 1.0
+}
+
+/// Copy of the sprite with everything outside the tile footprint cleared.
+pub fn mask_to_footprint(sprite: &Sprite) -> Sprite {
+    let mut out = sprite.clone();
+    for y in 0..sprite.height {
+        for x in 0..sprite.width {
+            if !diamond_contains(sprite.width, sprite.height, x, y) {
+                out.set_pixel(x, y, [0, 0, 0, 0]);
+            }
+        }
+    }
+    out
+}
+
+/// Size of the 2:1 diamond a `width` x `height` sprite projects onto.
+pub fn projected_size(width: u32, height: u32) -> (u32, u32) {
+    (width + height, (width + height).div_ceil(2))
+}
+
+/// Map the centre of output pixel (u, v) back to continuous sprite space.
+/// The projection puts the sprite's top corner at u = `sprite_height` and
+/// advances two pixels across for every pixel down, the usual pixel-art
+/// isometric line.
+fn unproject(u: u32, v: f32, sprite_height: u32) -> (f32, f32) {
+    let a = u as f32 + 0.5 - sprite_height as f32;
+    let b = 2.0 * (v + 0.5);
+    ((a + b) / 2.0, (b - a) / 2.0)
+}
+
+/// Bake a flat top-down sprite into its 2:1 isometric projection. Every
+/// output pixel takes the nearest source pixel, so edges stay crisp and a
+/// square texture lands exactly on the `diamond_contains` footprint.
+pub fn project_flat(sprite: &Sprite) -> Sprite {
+    let (w, h) = projected_size(sprite.width, sprite.height);
+    let mut out = Sprite::new(w, h);
+    for v in 0..h {
+        for u in 0..w {
+            let (fx, fy) = unproject(u, v as f32, sprite.height);
+            if fx < 0.0 || fy < 0.0 {
+                continue;
+            }
+            let (x, y) = (fx as u32, fy as u32);
+            if x < sprite.width && y < sprite.height {
+                out.set_pixel(u, v, sprite.get_pixel(x, y));
+            }
+        }
+    }
+    out
+}
+
+/// Rasterize the sprite as isometric blocks, extruding each pixel by its
+/// height. Transparent pixels are treated as empty space.
+pub fn render_blocks(sprite: &Sprite, heights: &HeightMap) -> Sprite {
+    let max_h = heights.max() as u32;
+    let (w, top_h) = projected_size(sprite.width, sprite.height);
+    let mut out = Sprite::new(w, top_h + max_h);
+
+    // Colour and height of the column at (x, y), if it holds a visible pixel
+    let column = |x: f32, y: f32| -> Option<([u8; 4], u32)> {
+        if x < 0.0 || y < 0.0 || x >= sprite.width as f32 || y >= sprite.height as f32 {
+            return None;
+        }
+        let color = sprite.get_pixel(x as u32, y as u32);
+        (color[3] > 0).then(|| (color, heights.get(x as u32, y as u32) as u32))
+    };
+
+    for v in 0..out.height {
+        for u in 0..out.width {
+            // Walk the pixel's ray from the top plane down. Between planes z + 1
+            // and z the ray moves one cell back diagonally, crossing either the
+            // +x or the +y neighbour of the cell it lands in.
+            for z in (0..=max_h).rev() {
+                let plane_v = v as f32 - max_h as f32 + z as f32;
+                let (fx, fy) = unproject(u, plane_v, sprite.height);
+                let (cx, cy) = (fx.floor(), fy.floor());
+                let (frac_x, frac_y) = (fx - cx, fy - cy);
+
+                let hit = if frac_x > frac_y {
+                    // Crossed y + 1 into the +x neighbour, then x + 1
+                    column(cx + 1.0, cy)
+                        .filter(|&(_, h)| h > z)
+                        .map(|(c, _)| shade(c, LEFT_FACE_SHADE))
+                } else {
+                    column(cx, cy + 1.0)
+                        .filter(|&(_, h)| h > z)
+                        .map(|(c, _)| shade(c, RIGHT_FACE_SHADE))
+                }
+                .or_else(|| {
+                    let (color, h) = column(cx, cy).filter(|&(_, h)| h >= z)?;
+                    Some(if h == z {
+                        color
+                    } else if frac_x > frac_y {
+                        shade(color, RIGHT_FACE_SHADE)
+                    } else {
+                        shade(color, LEFT_FACE_SHADE)
+                    })
+                });
+
+                if let Some(color) = hit {
+                    out.set_pixel(u, v, color);
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
@@ -1,10 +1,19 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod animation;
 mod app;
 mod canvas;
 mod command_palette;
+mod export;
+mod history;
 mod io;
+mod iso;
+mod notifications;
+mod recovery;
+mod selection;
+mod session;
 mod sprite;
+mod tileset;
 mod tools;
 
 fn main() {
@@ -13,7 +22,8 @@ fn main() {
         env_logger::init();
         let options = eframe::NativeOptions {
             viewport: egui::ViewportBuilder::default()
-                .with_inner_size([1280.0, 720.0]),
+                .with_inner_size([1280.0, 720.0])
+                .with_drag_and_drop(true),
             ..Default::default()
         };
         eframe::run_native(
@@ -0,0 +1,70 @@
+use egui::{Align2, Color32, RichText};
+
+/// Oldest notifications are dropped past this many.
+const MAX_VISIBLE: usize = 5;
+
+struct Notification {
+    id: u64,
+    text: String,
+}
+
+/// Errors stacked in the bottom-right corner until dismissed, so they
+/// aren't lost when the status bar moves on.
+#[derive(Default)]
+pub struct Notifications {
+    items: Vec<Notification>,
+    next_id: u64,
+}
+
+impl Notifications {
+    pub fn error(&mut self, error: impl std::fmt::Display) {
+        let text = error.to_string();
+        log::warn!("{text}");
+        // Repeating the last failure (e.g. retrying a save) adds nothing
+        if self.items.last().is_some_and(|n| n.text == text) {
+            return;
+        }
+        self.items.push(Notification {
+            id: self.next_id,
+            text,
+        });
+        self.next_id += 1;
+        if self.items.len() > MAX_VISIBLE {
+            self.items.remove(0);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.items.is_empty() {
+            return;
+        }
+        let mut dismissed = None;
+        let mut dismiss_all = false;
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(Align2::RIGHT_BOTTOM, [-12.0, -36.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(320.0);
+                for item in &self.items {
+                    egui::Frame::popup(ui.style())
+                        .fill(Color32::from_rgb(90, 30, 30))
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("x").on_hover_text("Dismiss").clicked() {
+                                    dismissed = Some(item.id);
+                                }
+                                ui.label(RichText::new(&item.text).color(Color32::WHITE));
+                            });
+                        });
+                }
+                if self.items.len() > 1 && ui.button("Dismiss all").clicked() {
+                    dismiss_all = true;
+                }
+            });
+        if dismiss_all {
+            self.items.clear();
+        } else if let Some(id) = dismissed {
+            self.items.retain(|n| n.id != id);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Periodic snapshots of the open documents, so a crash or a reloaded
+//! browser tab doesn't lose unsaved work. Natively each snapshot is a file
+//! in a recovery directory next to eframe's own storage; on the web they go
+//! to eframe storage (localStorage). Each document keeps its own snapshots
+//! until it's saved or its changes are thrown away.
+
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+use crate::animation::Animation;
+use crate::iso::HeightMap;
+use crate::session::Source;
+use crate::tileset::Tileset;
+
+/// How often to snapshot and how many snapshots to keep.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Seconds between snapshots, 0 turning autosave off
+    pub interval_secs: u32,
+    pub keep: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 60,
+            keep: 5,
+        }
+    }
+}
+
+/// Everything needed to bring a document back: its frames, block heights,
+/// tile data, swatches, PNG palette order and text, plus where it came from.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub animation: Animation,
+    pub heights: HeightMap,
+    pub tileset: Tileset,
+    pub palette: Option<Vec<[u8; 4]>>,
+    /// Missing from snapshots written before it was kept
+    #[serde(default)]
+    pub png_palette: Option<Vec<[u8; 4]>>,
+    pub png_text: Vec<(String, String)>,
+    pub pixels_per_grid: u32,
+    pub source: Option<Source>,
+}
+
+impl Document {
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+/// Where a snapshot is stored: when it was taken, of which document.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    pub time: u64,
+    /// Id of the tab the document was open in, see `first_document_id`
+    pub document: u64,
+}
+
+/// Start of this run's document ids. They count up from the start time in
+/// milliseconds so they don't repeat across runs.
+pub fn first_document_id() -> u64 {
+    now() * 1000
+}
+
+/// The newest snapshot of each document, newest first, from a list that is
+/// newest first.
+pub fn latest_per_document(snapshots: &[Snapshot]) -> Vec<Snapshot> {
+    let mut latest: Vec<Snapshot> = Vec::new();
+    for &snapshot in snapshots {
+        if latest.iter().all(|s| s.document != snapshot.document) {
+            latest.push(snapshot);
+        }
+    }
+    latest
+}
+
+/// Fingerprint of a serialized document, to skip snapshots of unchanged work.
+pub fn fingerprint(json: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    json.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Seconds since the Unix epoch. Snapshots are named and compared by it.
+pub fn now() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+}
+
+/// How long ago `time` was, for listing snapshots.
+pub fn age(time: u64) -> String {
+    let secs = now().saturating_sub(time);
+    match secs {
+        0..=59 => "just now".into(),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} days ago", secs / 86400),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native {
+    use std::path::PathBuf;
+
+    use super::{Document, Snapshot};
+    use crate::io::Error;
+
+    /// `recovery` inside eframe's data directory for the app.
+    fn dir() -> Result<PathBuf, Error> {
+        eframe::storage_dir("Spritedit")
+            .map(|dir| dir.join("recovery"))
+            .ok_or_else(|| Error::File("no data directory for recovery snapshots".into()))
+    }
+
+    /// `{time}-{document}.json`
+    fn file_name(snapshot: Snapshot) -> String {
+        format!("{}-{}.json", snapshot.time, snapshot.document)
+    }
+
+    fn parse_file_name(name: &str) -> Option<Snapshot> {
+        let (time, document) = name.strip_suffix(".json")?.split_once('-')?;
+        Some(Snapshot {
+            time: time.parse().ok()?,
+            document: document.parse().ok()?,
+        })
+    }
+
+    /// The snapshots on disk, newest first.
+    pub fn list() -> Vec<Snapshot> {
+        let Some(entries) = dir().ok().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+            return Vec::new();
+        };
+        let mut snapshots: Vec<Snapshot> = entries
+            .filter_map(|entry| parse_file_name(entry.ok()?.file_name().to_str()?))
+            .collect();
+        snapshots.sort_unstable_by_key(|snapshot| std::cmp::Reverse(snapshot.time));
+        snapshots
+    }
+
+    pub fn read(snapshot: Snapshot) -> Result<Document, Error> {
+        let path = dir()?.join(file_name(snapshot));
+        let json = std::fs::read_to_string(&path).map_err(|e| Error::from_io(e, &path))?;
+        Document::from_json(&json).map_err(|e| Error::corrupt("recovery snapshot", e))
+    }
+
+    /// Write a snapshot, then delete all but the document's newest `keep`.
+    /// The file is written under a temporary name first so a crash
+    /// mid-write can't leave a torn snapshot.
+    pub fn write(snapshot: Snapshot, json: &str, keep: usize) -> Result<(), Error> {
+        let dir = dir()?;
+        std::fs::create_dir_all(&dir).map_err(|e| Error::from_io(e, &dir))?;
+        let temp = dir.join(format!("{}-{}.tmp", snapshot.time, snapshot.document));
+        std::fs::write(&temp, json).map_err(|e| Error::from_io(e, &temp))?;
+        let path = dir.join(file_name(snapshot));
+        std::fs::rename(&temp, &path).map_err(|e| Error::from_io(e, &path))?;
+        let same_document = list().into_iter().filter(|old| old.document == snapshot.document);
+        for old in same_document.skip(keep.max(1)) {
+            let _ = std::fs::remove_file(dir.join(file_name(old)));
+        }
+        Ok(())
+    }
+
+    /// Delete the snapshots of one document, or of all with `None`.
+    pub fn clear(document: Option<u64>) -> Result<(), Error> {
+        let dir = dir()?;
+        for snapshot in list() {
+            if document.is_some_and(|document| document != snapshot.document) {
+                continue;
+            }
+            let path = dir.join(file_name(snapshot));
+            std::fs::remove_file(&path).map_err(|e| Error::from_io(e, &path))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub mod web {
+    use serde::{Deserialize, Serialize};
+
+    use super::{Document, Snapshot};
+    use crate::io::Error;
+
+    /// Storage key of the snapshot index. Snapshots themselves live in
+    /// numbered slots that get reused, since eframe storage can't delete
+    /// keys.
+    const INDEX_KEY: &str = "recovery";
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry {
+        time: u64,
+        #[serde(default)]
+        document: u64,
+        slot: usize,
+    }
+
+    impl Entry {
+        fn snapshot(&self) -> Snapshot {
+            Snapshot {
+                time: self.time,
+                document: self.document,
+            }
+        }
+    }
+
+    fn slot_key(slot: usize) -> String {
+        format!("recovery_{slot}")
+    }
+
+    /// Newest first
+    fn index(storage: &dyn eframe::Storage) -> Vec<Entry> {
+        eframe::get_value(storage, INDEX_KEY).unwrap_or_default()
+    }
+
+    /// The stored snapshots, newest first.
+    pub fn list(storage: &dyn eframe::Storage) -> Vec<Snapshot> {
+        index(storage).iter().map(Entry::snapshot).collect()
+    }
+
+    pub fn read(storage: &dyn eframe::Storage, snapshot: Snapshot) -> Result<Document, Error> {
+        let entry = index(storage).into_iter().find(|entry| entry.snapshot() == snapshot);
+        let json = entry
+            .and_then(|entry| storage.get_string(&slot_key(entry.slot)))
+            .ok_or_else(|| Error::File("recovery snapshot is gone".into()))?;
+        Document::from_json(&json).map_err(|e| Error::corrupt("recovery snapshot", e))
+    }
+
+    /// Store a snapshot in a free slot, blanking the slots of the
+    /// document's snapshots beyond its newest `keep`.
+    pub fn write(
+        storage: &mut dyn eframe::Storage,
+        snapshot: Snapshot,
+        json: &str,
+        keep: usize,
+    ) {
+        let mut entries = index(storage);
+        // The new snapshot takes one of the document's `keep`
+        let mut kept = 1;
+        entries.retain(|entry| {
+            if entry.document != snapshot.document {
+                return true;
+            }
+            kept += 1;
+            if kept <= keep.max(1) {
+                return true;
+            }
+            storage.set_string(&slot_key(entry.slot), String::new());
+            false
+        });
+        let slot = (0..).find(|slot| entries.iter().all(|e| e.slot != *slot)).unwrap_or(0);
+        storage.set_string(&slot_key(slot), json.to_string());
+        entries.insert(
+            0,
+            Entry {
+                time: snapshot.time,
+                document: snapshot.document,
+                slot,
+            },
+        );
+        eframe::set_value(storage, INDEX_KEY, &entries);
+    }
+
+    /// Blank the snapshots of one document, or of all with `None`.
+    pub fn clear(storage: &mut dyn eframe::Storage, document: Option<u64>) {
+        let mut entries = index(storage);
+        entries.retain(|entry| {
+            if document.is_some_and(|document| document != entry.document) {
+                return true;
+            }
+            storage.set_string(&slot_key(entry.slot), String::new());
+            false
+        });
+        eframe::set_value(storage, INDEX_KEY, &entries);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::sprite::{blend_over, Sprite};
+
+/// Rectangle `[x, y, width, height]` spanning two corner pixels, inclusive.
+pub fn bounds((x0, y0): (u32, u32), (x1, y1): (u32, u32)) -> [u32; 4] {
+    [
+        x0.min(x1),
+        y0.min(y1),
+        x0.abs_diff(x1) + 1,
+        y0.abs_diff(y1) + 1,
+    ]
+}
+
+/// Pixels held above the sprite, e.g. a paste, until they're dropped onto
+/// it. The position may lie partly off the sprite.
+#[derive(Clone)]
+pub struct Floating {
+    pub sprite: Sprite,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Floating {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && x < self.x + self.sprite.width as i32
+            && y < self.y + self.sprite.height as i32
+    }
+
+    /// Composite onto `target`, clipping whatever falls outside it.
+    pub fn stamp(&self, target: &mut Sprite) {
+        for y in 0..self.sprite.height {
+            for x in 0..self.sprite.width {
+                let (tx, ty) = (self.x + x as i32, self.y + y as i32);
+                if tx < 0 || ty < 0 || tx >= target.width as i32 || ty >= target.height as i32 {
+                    continue;
+                }
+                let (tx, ty) = (tx as u32, ty as u32);
+                let under = target.get_pixel(tx, ty);
+                target.set_pixel(tx, ty, blend_over(self.sprite.get_pixel(x, y), under));
+            }
+        }
+    }
+}
+
+/// What the current select tool drag is doing.
+#[derive(Clone, Copy)]
+pub enum Drag {
+    /// Stretching a new selection from this corner
+    Marquee((u32, u32)),
+    /// Moving the floating pixels, last seen under this canvas pixel
+    Move((i32, i32)),
+}
@@ -0,0 +1,115 @@
+//! What Spritedit remembers between launches: recently opened files, the
+//! last session's document and view, and autosave settings.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::canvas::{CanvasState, TileMode};
+use crate::recovery;
+
+/// eframe storage key for `Session`.
+pub const STORAGE_KEY: &str = "session";
+pub const MAX_RECENT: usize = 10;
+
+/// Where a document was loaded from, so it can be opened again. Files
+/// picked in the browser have no path and aren't remembered.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Source {
+    File(PathBuf),
+    Url(String),
+}
+
+impl Source {
+    /// Short name for menus: the file name, or the last part of the URL.
+    pub fn label(&self) -> String {
+        match self {
+            Source::File(path) => path
+                .file_name()
+                .map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned()),
+            Source::Url(url) => {
+                let path = url.split(['?', '#']).next().unwrap_or(url);
+                match path.trim_end_matches('/').rsplit('/').next() {
+                    Some(name) if !name.is_empty() => name.to_string(),
+                    _ => url.clone(),
+                }
+            }
+        }
+    }
+
+    /// Full path or URL, for hover text.
+    pub fn full(&self) -> String {
+        match self {
+            Source::File(path) => path.display().to_string(),
+            Source::Url(url) => url.clone(),
+        }
+    }
+}
+
+/// The parts of `CanvasState` that describe how the user looks at the
+/// sprite, as opposed to guides other features draw.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ViewState {
+    pub zoom: f32,
+    pub offset: [f32; 2],
+    pub show_grid: bool,
+    pub isometric: bool,
+    pub blocks: bool,
+    pub tiled: bool,
+    pub tile_mode: TileMode,
+    pub show_footprint: bool,
+    pub lock_footprint: bool,
+    pub pixels_per_grid: u32,
+}
+
+impl ViewState {
+    pub fn capture(state: &CanvasState) -> Self {
+        Self {
+            zoom: state.zoom,
+            offset: [state.offset.x, state.offset.y],
+            show_grid: state.show_grid,
+            isometric: state.isometric,
+            blocks: state.blocks,
+            tiled: state.tiled,
+            tile_mode: state.tile_mode,
+            show_footprint: state.show_footprint,
+            lock_footprint: state.lock_footprint,
+            pixels_per_grid: state.pixels_per_grid,
+        }
+    }
+
+    pub fn apply(&self, state: &mut CanvasState) {
+        state.zoom = self.zoom.clamp(2.0, 128.0);
+        state.offset = egui::vec2(self.offset[0], self.offset[1]);
+        state.show_grid = self.show_grid;
+        state.isometric = self.isometric;
+        state.blocks = self.blocks;
+        state.tiled = self.tiled;
+        state.tile_mode = self.tile_mode;
+        state.show_footprint = self.show_footprint;
+        state.lock_footprint = self.lock_footprint;
+        state.pixels_per_grid = self.pixels_per_grid.clamp(1, 32);
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Most recent first
+    pub recent: Vec<Source>,
+    /// Open the last document with its view on startup
+    pub reopen_last: bool,
+    /// Document open when the app last closed, if it had a source
+    pub last: Option<Source>,
+    pub view: Option<ViewState>,
+    pub autosave: recovery::Settings,
+}
+
+impl Session {
+    /// Move `source` to the top of the recent list.
+    pub fn add_recent(&mut self, source: Source) {
+        self.recent.retain(|s| *s != source);
+        self.recent.insert(0, source);
+        self.recent.truncate(MAX_RECENT);
+    }
+}
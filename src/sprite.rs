@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Sprite {
     pub width: u32,
     pub height: u32,
@@ -17,6 +17,32 @@ impl Sprite {
         }
     }
 
+    /// Copy into a `width` x `height` sprite at the top-left corner,
+    /// cropping or padding with transparency.
+    pub fn with_size(&self, width: u32, height: u32) -> Sprite {
+        let mut out = Sprite::new(width, height);
+        let row = (width.min(self.width) * 4) as usize;
+        for y in 0..height.min(self.height) {
+            let src = (y * self.width * 4) as usize;
+            let dst = (y * width * 4) as usize;
+            out.pixels[dst..dst + row].copy_from_slice(&self.pixels[src..src + row]);
+        }
+        out
+    }
+
+    /// Copy out the rectangle `[x, y, width, height]`, which must lie
+    /// within the sprite.
+    pub fn crop(&self, [x, y, width, height]: [u32; 4]) -> Sprite {
+        let mut out = Sprite::new(width, height);
+        let row = (width * 4) as usize;
+        for dy in 0..height {
+            let src = (((y + dy) * self.width + x) * 4) as usize;
+            let dst = dy as usize * row;
+            out.pixels[dst..dst + row].copy_from_slice(&self.pixels[src..src + row]);
+        }
+        out
+    }
+
     pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
         let idx = ((y * self.width + x) * 4) as usize;
         [
@@ -35,6 +61,17 @@ impl Sprite {
     }
 
     pub fn flood_fill(&mut self, x: u32, y: u32, fill_color: [u8; 4]) {
+        self.flood_fill_within(x, y, fill_color, |_, _| true);
+    }
+
+    /// Flood fill that never spreads onto pixels rejected by `mask`.
+    pub fn flood_fill_within(
+        &mut self,
+        x: u32,
+        y: u32,
+        fill_color: [u8; 4],
+        mask: impl Fn(u32, u32) -> bool,
+    ) {
         let target_color = self.get_pixel(x, y);
         if target_color == fill_color {
             return;
@@ -44,7 +81,7 @@ impl Sprite {
             if cx >= self.width || cy >= self.height {
                 continue;
             }
-            if self.get_pixel(cx, cy) != target_color {
+            if self.get_pixel(cx, cy) != target_color || !mask(cx, cy) {
                 continue;
             }
             self.set_pixel(cx, cy, fill_color);
@@ -66,3 +103,22 @@ impl Sprite {
         )
     }
 }
+
+/// Straight-alpha "source over" compositing.
+pub fn blend_over(src: [u8; 4], dst: [u8; 4]) -> [u8; 4] {
+    let sa = src[3] as f32 / 255.0;
+    let da = dst[3] as f32 / 255.0;
+    let out_a = sa + da * (1.0 - sa);
+    if out_a <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let channel = |s: u8, d: u8| {
+        ((s as f32 * sa + d as f32 * da * (1.0 - sa)) / out_a).round() as u8
+    };
+    [
+        channel(src[0], dst[0]),
+        channel(src[1], dst[1]),
+        channel(src[2], dst[2]),
+        (out_a * 255.0).round() as u8,
+    ]
+}
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Metadata authored for one tile of the `pixels_per_grid` grid.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TileData {
+    /// Name/value pairs, kept in the order they were added
+    pub properties: Vec<(String, String)>,
+    /// Collision rectangles `[x, y, width, height]` relative to the tile
+    pub collision: Vec<[u32; 4]>,
+}
+
+impl TileData {
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty() && self.collision.is_empty()
+    }
+}
+
+/// Per-tile metadata for the sprite, keyed by tile index counted row by row
+/// across the tile grid.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tileset {
+    pub tiles: BTreeMap<u32, TileData>,
+}
+
+impl Tileset {
+    pub fn get(&self, index: u32) -> Option<&TileData> {
+        self.tiles.get(&index)
+    }
+
+    pub fn tile_mut(&mut self, index: u32) -> &mut TileData {
+        self.tiles.entry(index).or_default()
+    }
+
+    /// Drop tiles left with no properties or collision.
+    pub fn prune(&mut self) {
+        self.tiles.retain(|_, tile| !tile.is_empty());
+    }
+}
@@ -4,6 +4,9 @@ pub enum Tool {
     Eraser,
     Fill,
     ColorPicker,
+    Raise,
+    Lower,
+    Select,
 }
 
 impl Tool {
@@ -13,6 +16,9 @@ impl Tool {
             Tool::Eraser => "Eraser",
             Tool::Fill => "Fill",
             Tool::ColorPicker => "Pick Color",
+            Tool::Raise => "Raise Block",
+            Tool::Lower => "Lower Block",
+            Tool::Select => "Select",
         }
     }
 
@@ -22,6 +28,9 @@ impl Tool {
             Tool::Eraser => "E",
             Tool::Fill => "F",
             Tool::ColorPicker => "I",
+            Tool::Raise => "R",
+            Tool::Lower => "L",
+            Tool::Select => "M",
         }
     }
 
@@ -31,6 +40,9 @@ impl Tool {
             Tool::Eraser => "\u{2B1C}",
             Tool::Fill => "\u{2B24}",
             Tool::ColorPicker => "\u{25C9}",
+            Tool::Raise => "\u{25B2}",
+            Tool::Lower => "\u{25BC}",
+            Tool::Select => "\u{2B1A}",
         }
     }
 }